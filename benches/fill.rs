@@ -0,0 +1,36 @@
+use std::time::Instant;
+
+use minesweeper::MineField;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+const BOARDS: u64 = 500;
+
+/// Times placing 98 mines on a 10x10 board by picking random cells against `fill`
+fn main() {
+    // The previous placement, picking random cells until enough free ones are hit
+    let start = Instant::now();
+    for seed in 0..BOARDS {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut is_mine = [false; 100];
+        let mut placed_mines = 0;
+        while placed_mines < 98 {
+            let index = rng.random_range(0..100);
+            if index == 55 || is_mine[index] {
+                continue;
+            }
+            is_mine[index] = true;
+            placed_mines += 1;
+        }
+    }
+    let rejection = start.elapsed();
+
+    let start = Instant::now();
+    for seed in 0..BOARDS {
+        let mut mine_field = MineField::new_seeded(10, 10, 98, seed).unwrap();
+        mine_field.fill(5, 5);
+    }
+    let shuffle = start.elapsed();
+
+    println!("98 mines on 10x10, {} boards: rejection {:?}, shuffle {:?}", BOARDS, rejection, shuffle);
+}
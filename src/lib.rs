@@ -0,0 +1,3198 @@
+mod replay;
+mod scores;
+#[cfg(feature = "serde")]
+mod serialize;
+mod solver;
+mod stats;
+mod transform;
+
+use log::{debug, info, trace, warn};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use rand::Rng;
+use rand::seq::{IndexedRandom, IteratorRandom, SliceRandom};
+use std::error::Error;
+use std::fmt::{self, Write as _};
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io::{self, IsTerminal};
+use std::mem;
+use std::ops::Range;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+pub use replay::Action;
+pub use scores::{Scores, load_scores, save_scores};
+pub use stats::Stats;
+
+const MAX_OPENING_ATTEMPTS: usize = 1000;  // Boards generated before giving up on a large enough first opening
+
+// ANSI escape codes used to color the board
+const COLOR_RESET: &str = "\x1b[0m";
+const COLOR_MINE: &str = "\x1b[91m";  // Bright red
+const COLOR_FLAG: &str = "\x1b[93m";  // Yellow
+const COLOR_WIN: &str = "\x1b[92m";   // Bright green
+const COLOR_PEEK: &str = "\x1b[2m";   // Dim
+const COLOR_CURSOR: &str = "\x1b[7m"; // Reverse video
+const COLOR_NUMBERS: [&str; 8] = [
+    "\x1b[94m",  // 1 - blue
+    "\x1b[32m",  // 2 - green
+    "\x1b[31m",  // 3 - red
+    "\x1b[34m",  // 4 - dark blue
+    "\x1b[35m",  // 5 - maroon
+    "\x1b[36m",  // 6 - cyan
+    "\x1b[37m",  // 7 - black (light gray, so it stays visible on dark terminals)
+    "\x1b[90m",  // 8 - gray
+];
+
+/// Standard game presets
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Difficulty {
+    Beginner,       // 9x9 with 10 mines
+    Intermediate,   // 16x16 with 40 mines
+    Expert,         // 30x16 with 99 mines
+    Custom,         // Size and mine count chosen by the player
+}
+
+impl Difficulty {
+    /// Returns the (width, height, mine count) of a preset, or None for a custom game
+    pub fn settings(self) -> Option<(usize, usize, usize)> {
+        match self {
+            Difficulty::Beginner => Some((9, 9, 10)),
+            Difficulty::Intermediate => Some((16, 16, 40)),
+            Difficulty::Expert => Some((30, 16, 99)),
+            Difficulty::Custom => None,
+        }
+    }
+}
+
+impl FromStr for Difficulty {
+    type Err = ();
+
+    /// Parses a difficulty from its name or first letter, ignoring case
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "b" | "beginner" => Ok(Difficulty::Beginner),
+            "i" | "intermediate" => Ok(Difficulty::Intermediate),
+            "e" | "expert" => Ok(Difficulty::Expert),
+            "c" | "custom" => Ok(Difficulty::Custom),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Errors that can occur while creating or playing on a mine field
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BoardError {
+    Empty,                      // The mine field would have no rows or columns
+    OutOfBounds(usize, usize),  // The coordinates lie outside the mine field or on a hole
+    GameOver,                   // The game has already been won or lost
+    InvalidDensity,             // The mine density is not strictly between 0 and 1
+}
+
+impl fmt::Display for BoardError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BoardError::Empty => write!(f, "the mine field must have at least one row and column"),
+            BoardError::OutOfBounds(x, y) => write!(f, "the coordinates ({}, {}) are outside the mine field", x, y),
+            BoardError::GameOver => write!(f, "the game is already over"),
+            BoardError::InvalidDensity => write!(f, "the mine density must be between 0 and 1"),
+        }
+    }
+}
+
+impl Error for BoardError {}
+
+/// Errors that can occur while loading a mine field from text
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseError {
+    Empty,                           // The text contains no rows
+    InvalidCharacter(char),          // A character other than `*`, `.` or `#` was found
+    InconsistentRowLength(usize),    // The row at the given index differs in length from the first row
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::Empty => write!(f, "the mine field has no rows"),
+            ParseError::InvalidCharacter(c) => write!(f, "invalid character '{}', expected '*', '.' or '#'", c),
+            ParseError::InconsistentRowLength(row) => write!(f, "row {} has a different length than the first row", row + 1),
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+/// The characters used to print the board
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Theme {
+    pub mine: &'static str,            // A revealed mine
+    pub flag: &'static str,            // A flagged cell
+    pub wrong_flag: &'static str,      // A flag on a cell without a mine, shown once the game is lost
+    pub question: &'static str,        // A question-marked cell
+    pub unrevealed: &'static str,      // A hidden cell
+    pub revealed_empty: &'static str,  // A revealed cell without adjacent mines
+    pub numbers: [&'static str; 8],    // Revealed cells with 1 to 8 adjacent mines
+    pub border: [&'static str; 6],     // Top left, top right, bottom left, bottom right, horizontal, vertical
+}
+
+impl Theme {
+    /// Plain ASCII characters that work in every terminal
+    pub const ASCII: Theme = Theme {
+        mine: "*",
+        flag: "F",
+        wrong_flag: "X",
+        question: "?",
+        unrevealed: " ",
+        revealed_empty: ".",
+        numbers: ["1", "2", "3", "4", "5", "6", "7", "8"],
+        border: ["+", "+", "+", "+", "-", "|"],
+    };
+
+    /// Unicode box-drawing characters and glyphs
+    pub const UNICODE: Theme = Theme {
+        mine: "💣",
+        flag: "⚑",
+        border: ["┌", "┐", "└", "┘", "─", "│"],
+        ..Theme::ASCII
+    };
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::ASCII
+    }
+}
+
+/// Whether the game is still going on or how it ended
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GameState {
+    #[default]
+    Playing,    // The game goes on
+    Won,        // Every non-mine cell has been revealed
+    Lost,       // A mine was revealed
+}
+
+/// Which surrounding cells count as neighbors, both for mine counts and for opening empty regions
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Adjacency {
+    #[default]
+    King,         // The 8 cells a chess king can move to
+    Knight,       // The 8 cells a chess knight can move to
+    VonNeumann,   // The 4 orthogonal cells
+}
+
+impl Adjacency {
+    /// Returns the (dx, dy) offsets of the neighbors of a cell
+    fn offsets(self) -> &'static [(isize, isize)] {
+        match self {
+            Adjacency::King => &[(-1, -1), (0, -1), (1, -1), (-1, 0), (1, 0), (-1, 1), (0, 1), (1, 1)],
+            Adjacency::Knight => &[(-1, -2), (1, -2), (-2, -1), (2, -1), (-2, 1), (2, 1), (-1, 2), (1, 2)],
+            Adjacency::VonNeumann => &[(0, -1), (-1, 0), (1, 0), (0, 1)],
+        }
+    }
+}
+
+/// How mines are spread over the board when it is filled
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Distribution {
+    #[default]
+    Uniform,      // Every candidate cell is equally likely to hold a mine
+    Clustered,    // Mines gather around a few random centers
+}
+
+/// What it takes to win a game
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum WinRule {
+    #[default]
+    RevealSafe,   // Every non-mine cell has been revealed
+    Strict,       // Additionally every mine is flagged and no other cell is
+}
+
+/// How the columns of the board are labeled when printing it
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColumnLabels {
+    #[default]
+    Numeric,      // 1, 2, 3 and so on
+    Alphabetic,   // A, B, C and so on, continuing with AA after Z
+}
+
+/// A mark the player can place on an unrevealed cell
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Mark {
+    #[default]
+    None,       // No mark
+    Flag,       // A suspected mine, protected from being revealed
+    Question,   // An unsure guess, can still be revealed
+}
+
+/// A single cell of the mine field
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Cell {
+    pub is_mine: bool,       // Whether the cell holds a mine
+    pub is_revealed: bool,   // Whether the player has uncovered the cell
+    pub mark: Mark,          // The mark placed on the cell by the player
+    pub adjacent_mines: u8,  // Number of mines in the surrounding cells
+}
+
+impl Cell {
+    /// Returns true if the player has flagged the cell as a suspected mine
+    pub fn is_flagged(&self) -> bool {
+        self.mark == Mark::Flag
+    }
+}
+
+/// What the player can see of a single cell
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CellView {
+    Hidden,         // An unrevealed cell without a mark
+    Flagged,        // An unrevealed cell marked as a suspected mine
+    Question,       // An unrevealed cell marked as an unsure guess
+    Revealed(u8),   // A revealed safe cell with the number of adjacent mines
+    RevealedMine,   // A revealed mine
+}
+
+/// Represents the minesweeper game board
+#[derive(Clone)]
+pub struct MineField {
+    field: Box<[Box<[Cell]>]>,  // 2D array of cells
+    width: usize,               // Number of columns
+    height: usize,              // Number of rows
+    mine_count: usize,          // Total number of mines on the board
+    rng: StdRng,                // Random number generator used to place mines
+    use_color: bool,            // Whether to print the board with ANSI colors
+    theme: Theme,               // Characters used to print the board
+    wrap: bool,                 // Whether the board edges wrap around (toroidal adjacency)
+    moves: usize,               // Number of reveal and chord actions taken by the player
+    start_time: Option<Instant>,  // When the first cell was revealed
+    history: Vec<Vec<(usize, usize)>>,  // Cells revealed by each reveal and chord, most recent last
+    actions: Vec<Action>,       // Every move made by the player, oldest first
+    mask: Option<Box<[Box<[bool]>]>>,  // Which cells are part of the board, `None` if all of them are
+    adjacency: Adjacency,       // Which surrounding cells count as neighbors
+    state: GameState,           // Whether the game is still going on or how it ended
+    timed_out: bool,            // Whether the game was lost by running out of time instead of hitting a mine
+    win_rule: WinRule,          // What it takes to win the game
+    flood: bool,                // Whether revealing an empty cell also opens its surroundings
+    cell_width: usize,          // Number of terminal columns each cell is printed in
+    safe_radius: usize,         // Rings of cells around the first revealed cell kept free of mines
+    min_first_opening: usize,   // Cells the first reveal has to open, met by placing the mines again
+    peek: bool,                 // Whether hidden mines are shown when printing, for debugging
+    flag_overlay: bool,         // Whether revealed numbers are printed with the flags around them
+    column_labels: ColumnLabels,  // How the columns are labeled when printing
+    bottom_origin: bool,        // Whether rows are numbered from the bottom instead of the top
+    auto_flag: bool,            // Whether the unflagged mines are flagged once the game is won
+    auto_chord_on_revealed: bool,  // Whether revealing a revealed number chords it
+    distribution: Distribution, // How mines are spread over the board
+    cursor: Option<(usize, usize)>,  // Cell highlighted when printing, for keyboard navigation
+    margins: bool,              // Whether the number of mines in each row and column is printed
+    revealed_safe: usize,       // Number of revealed non-mine cells, kept up to date by every reveal
+    total_safe: usize,          // Number of non-mine cells, recounted when the mines or holes change
+}
+
+impl MineField {
+    /// Creates a new empty mine field with the specified dimensions
+    /// Returns an error if the mine field would have no cells
+    pub fn new(width: usize, height: usize, mine_count: usize) -> Result<Self, BoardError> {
+        Self::with_rng(width, height, mine_count, StdRng::from_os_rng())
+    }
+
+    /// Creates a new empty mine field whose mines are placed reproducibly from the given seed
+    /// Returns an error if the mine field would have no cells
+    pub fn new_seeded(width: usize, height: usize, mine_count: usize, seed: u64) -> Result<Self, BoardError> {
+        Self::with_rng(width, height, mine_count, StdRng::seed_from_u64(seed))
+    }
+
+    /// Creates a new empty mine field with `density` of its cells, rounded, being mines
+    /// Returns an error if the mine field would have no cells or the density is not between 0 and 1
+    pub fn from_density(width: usize, height: usize, density: f64) -> Result<Self, BoardError> {
+        Self::new(width, height, Self::density_mine_count(width, height, density)?)
+    }
+
+    /// Number of mines that makes up `density` of a `width` by `height` mine field
+    /// Clamped so there is at least one mine and at least one safe cell
+    /// Returns an error if the density is not strictly between 0 and 1
+    pub fn density_mine_count(width: usize, height: usize, density: f64) -> Result<usize, BoardError> {
+        if !(density > 0.0 && density < 1.0) {
+            return Err(BoardError::InvalidDensity);
+        }
+        let cells = width * height;
+        let mine_count = (cells as f64 * density).round() as usize;
+        Ok(mine_count.max(1).min(cells.saturating_sub(1)))
+    }
+
+    /// Creates `count` filled mine fields for benchmarking solvers, seeded with `base_seed`, `base_seed + 1`
+    /// and so on so the same base seed always gives the same boards
+    /// The mines of every board are placed around its center cell, which is where the solver should start
+    /// Returns an error if the mine fields would have no cells
+    pub fn generate_batch(
+        width: usize,
+        height: usize,
+        mine_count: usize,
+        count: usize,
+        base_seed: u64,
+    ) -> Result<Vec<Self>, BoardError> {
+        (0..count as u64)
+            .map(|i| {
+                let mut mine_field = Self::new_seeded(width, height, mine_count, base_seed.wrapping_add(i))?;
+                mine_field.fill(width / 2, height / 2);
+                Ok(mine_field)
+            })
+            .collect()
+    }
+
+    /// Creates a new empty mine field that places its mines using the given random number generator
+    fn with_rng(width: usize, height: usize, mine_count: usize, rng: StdRng) -> Result<Self, BoardError> {
+        if width == 0 || height == 0 {
+            return Err(BoardError::Empty);
+        }
+
+        let field = (0..height)
+            .map(|_| vec![Cell::default(); width].into_boxed_slice())
+            .collect::<Vec<_>>()
+            .into_boxed_slice();
+
+        // Only use colors when printing to a terminal, which a WebAssembly build never has
+        let use_color = !cfg!(target_arch = "wasm32") && io::stdout().is_terminal();
+
+        let mut mine_field = Self {
+            field,
+            width,
+            height,
+            mine_count,
+            rng,
+            use_color,
+            theme: Theme::default(),
+            wrap: false,
+            moves: 0,
+            start_time: None,
+            history: Vec::new(),
+            actions: Vec::new(),
+            mask: None,
+            adjacency: Adjacency::default(),
+            state: GameState::default(),
+            timed_out: false,
+            win_rule: WinRule::default(),
+            flood: true,
+            cell_width: 3,
+            safe_radius: 1,
+            min_first_opening: 0,
+            peek: false,
+            flag_overlay: false,
+            column_labels: ColumnLabels::Numeric,
+            bottom_origin: false,
+            auto_flag: false,
+            auto_chord_on_revealed: false,
+            distribution: Distribution::Uniform,
+            cursor: None,
+            margins: false,
+            revealed_safe: 0,
+            total_safe: 0,
+        };
+        mine_field.count_safe_cells();
+        Ok(mine_field)
+    }
+
+    /// Fills the mine field with mines and calculates adjacent mine counts
+    /// The cell at (safe_x, safe_y) never receives a mine, and neither do the cells within the safe radius
+    /// around it if there is room, so by default the first reveal lands on an empty cell
+    /// Flags and question marks placed before filling are kept
+    pub fn fill(&mut self, safe_x: usize, safe_y: usize) {
+        // Take the generator out for the duration so it can be borrowed alongside the board
+        let mut rng = mem::replace(&mut self.rng, StdRng::seed_from_u64(0));
+        self.fill_with_rng(safe_x, safe_y, &mut rng);
+        self.rng = rng;
+    }
+
+    /// Fills the mine field like `fill`, but places the mines using the given random number generator
+    /// instead of the one the mine field was created with, so tests can supply a deterministic source
+    pub fn fill_with_rng<R: Rng + ?Sized>(&mut self, safe_x: usize, safe_y: usize, rng: &mut R) {
+        // Marks placed before the first reveal survive clearing the board, and so do the moves placing them
+        let marks = self.marks();
+        let flag_actions: Vec<Action> =
+            self.actions.iter().copied().filter(|action| matches!(action, Action::Flag { .. })).collect();
+
+        let width = self.width;
+        let height = self.height;
+
+        // Holes cut out by the mask never receive a mine
+        let active_cells: Vec<(usize, usize)> = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .filter(|&(x, y)| self.is_active(x, y))
+            .collect();
+
+        // Shrink the safe region until enough other cells remain for the mines
+        let mut radius = self.safe_radius.min(width.max(height));
+        let safe_cells = loop {
+            let region = self.safe_region(safe_x, safe_y, radius);
+            if radius == 0 || active_cells.len().saturating_sub(region.len()) >= self.mine_count {
+                break region;
+            }
+            radius -= 1;
+        };
+
+        let mut candidates: Vec<(usize, usize)> = active_cells
+            .into_iter()
+            .filter(|cell| !safe_cells.contains(cell))
+            .collect();
+        let mine_count = self.mine_count.min(candidates.len());
+
+        // Place the mines again until the first reveal opens enough cells,
+        // keeping the last board if none does within the attempt limit
+        for attempt in 1..=MAX_OPENING_ATTEMPTS {
+            self.zero();
+            let mines: Vec<(usize, usize)> = match self.distribution {
+                // Shuffle the cells that may hold a mine and take the first ones, so dense boards
+                // don't slow down the way picking random cells until a free one is hit does
+                Distribution::Uniform => candidates.partial_shuffle(rng, mine_count).0.to_vec(),
+                Distribution::Clustered => clustered_mines(&candidates, mine_count, rng),
+            };
+
+            for (x, y) in mines {
+                self.place_mine(x, y);
+            }
+            let opening = self.opening_size(safe_x, safe_y);
+            if opening >= self.min_first_opening {
+                debug!(
+                    "placed {} mines around ({}, {}) in {} attempt(s), opening {} cells",
+                    mine_count, safe_x, safe_y, attempt, opening
+                );
+                break;
+            }
+            trace!("attempt {} opens {} of the {} cells asked for", attempt, opening, self.min_first_opening);
+            if attempt == MAX_OPENING_ATTEMPTS {
+                warn!(
+                    "no board opens {} cells from ({}, {}), keeping the last one",
+                    self.min_first_opening, safe_x, safe_y
+                );
+            }
+        }
+        self.restore_marks(&marks);
+        self.actions = flag_actions;
+        self.count_safe_cells();
+    }
+
+    /// Returns the mark of every cell, row by row
+    fn marks(&self) -> Vec<Mark> {
+        self.field.iter().flatten().map(|cell| cell.mark).collect()
+    }
+
+    /// Puts back marks returned by `marks`
+    fn restore_marks(&mut self, marks: &[Mark]) {
+        for (cell, &mark) in self.field.iter_mut().flatten().zip(marks) {
+            cell.mark = mark;
+        }
+    }
+
+    /// Returns the active cells at most `radius` rows and columns away from the safe cell
+    /// Any radius above 0 also covers the neighbors of the safe cell, so it opens up with every adjacency
+    fn safe_region(&self, safe_x: usize, safe_y: usize, radius: usize) -> Vec<(usize, usize)> {
+        // The distance across a wrapped edge can be shorter than the one within the board
+        let distance = |a: usize, b: usize, size: usize| {
+            let distance = a.abs_diff(b);
+            if self.wrap { distance.min(size - distance) } else { distance }
+        };
+
+        let mut region: Vec<(usize, usize)> = self
+            .cells()
+            .filter(|&(x, y, _)| {
+                self.is_active(x, y)
+                    && distance(x, safe_x, self.width) <= radius
+                    && distance(y, safe_y, self.height) <= radius
+            })
+            .map(|(x, y, _)| (x, y))
+            .collect();
+        if radius > 0 {
+            for neighbor in self.neighbors(safe_x, safe_y) {
+                if !region.contains(&neighbor) {
+                    region.push(neighbor);
+                }
+            }
+        }
+        region
+    }
+
+    /// Places a mine on the given cell and updates the adjacent cell counts
+    fn place_mine(&mut self, x: usize, y: usize) {
+        self.field[y][x].is_mine = true;
+        for (x, y) in self.neighbors(x, y) {
+            self.field[y][x].adjacent_mines += 1;
+        }
+    }
+
+    /// Recounts the adjacent mines of every cell from the mine positions alone
+    /// Useful after changing the adjacency, wrapping or mask of a board whose mines are already placed
+    pub fn recompute_adjacency(&mut self) {
+        for cell in self.field.iter_mut().flatten() {
+            cell.adjacent_mines = 0;
+        }
+
+        let mines: Vec<(usize, usize)> = self.cells().filter(|(_, _, cell)| cell.is_mine).map(|(x, y, _)| (x, y)).collect();
+        for (x, y) in mines {
+            self.place_mine(x, y);
+        }
+    }
+
+    /// Checks that the adjacent mine count of every non-mine cell matches the mines around it
+    /// Returns the cells whose stored count is wrong
+    pub fn validate(&self) -> Result<(), Vec<(usize, usize)>> {
+        let inconsistent: Vec<(usize, usize)> = self
+            .cells()
+            .filter(|&(x, y, cell)| self.is_active(x, y) && !cell.is_mine)
+            .filter(|&(x, y, cell)| {
+                let mines = self.neighbors(x, y).filter(|&(x, y)| self.field[y][x].is_mine).count();
+                cell.adjacent_mines as usize != mines
+            })
+            .map(|(x, y, _)| (x, y))
+            .collect();
+
+        if inconsistent.is_empty() { Ok(()) } else { Err(inconsistent) }
+    }
+
+    /// Returns the solved board as text regardless of what has been revealed,
+    /// with `*` for mines, digits for adjacent mine counts, spaces for empty cells and `#` for holes
+    pub fn to_solution_string(&self) -> String {
+        let mut solution = String::new();
+        for (y, row) in self.field.iter().enumerate() {
+            for (x, cell) in row.iter().enumerate() {
+                solution.push(match cell {
+                    _ if !self.is_active(x, y) => '#',
+                    Cell { is_mine: true, .. } => '*',
+                    Cell { adjacent_mines: 0, .. } => ' ',
+                    Cell { adjacent_mines: n, .. } => char::from(b'0' + n),
+                });
+            }
+            solution.push('\n');
+        }
+        solution
+    }
+
+    /// Returns the mine layout as text, with `*` for mines, `.` for empty cells and `#` for holes
+    pub fn to_layout_string(&self) -> String {
+        let mut layout = String::new();
+        for (y, row) in self.field.iter().enumerate() {
+            for (x, cell) in row.iter().enumerate() {
+                layout.push(match cell.is_mine {
+                    _ if !self.is_active(x, y) => '#',
+                    true => '*',
+                    false => '.',
+                });
+            }
+            layout.push('\n');
+        }
+        layout
+    }
+
+    /// Returns the number of columns of the mine field
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Returns the number of rows of the mine field
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Returns the total number of mines on the board
+    pub fn mine_count(&self) -> usize {
+        self.mine_count
+    }
+
+    /// Returns every cell with its x and y coordinates, row by row
+    pub fn cells(&self) -> impl Iterator<Item = (usize, usize, &Cell)> {
+        self.field
+            .iter()
+            .enumerate()
+            .flat_map(|(y, row)| row.iter().enumerate().map(move |(x, cell)| (x, y, cell)))
+    }
+
+    /// Converts a row-major index into the cells to x and y coordinates
+    /// Indices past the last cell give a row below the board
+    pub fn index_to_xy(&self, index: usize) -> (usize, usize) {
+        (index % self.width, index / self.width)
+    }
+
+    /// Converts x and y coordinates to a row-major index into the cells
+    pub fn xy_to_index(&self, x: usize, y: usize) -> usize {
+        y * self.width + x
+    }
+
+    /// Returns what the player sees of the cell at the given coordinates,
+    /// `None` if they lie outside the mine field or on a hole
+    pub fn cell_at(&self, x: usize, y: usize) -> Option<CellView> {
+        self.check_bounds(x, y).ok()?;
+        let cell = self.field[y][x];
+        Some(match cell {
+            Cell { is_revealed: true, is_mine: true, .. } => CellView::RevealedMine,
+            Cell { is_revealed: true, adjacent_mines, .. } => CellView::Revealed(adjacent_mines),
+            Cell { mark: Mark::Flag, .. } => CellView::Flagged,
+            Cell { mark: Mark::Question, .. } => CellView::Question,
+            Cell { mark: Mark::None, .. } => CellView::Hidden,
+        })
+    }
+
+    /// Returns true if the cell is part of the board, false for holes cut out by the mask
+    pub fn is_active(&self, x: usize, y: usize) -> bool {
+        self.mask.as_ref().is_none_or(|mask| mask[y][x])
+    }
+
+    /// Shapes the board by keeping only the cells for which `is_active` returns true
+    /// The other cells become holes that never hold a mine, aren't neighbors of any cell and
+    /// don't need to be revealed to win
+    /// Takes effect the next time mines are placed, so it should be set before `fill`
+    pub fn set_mask(&mut self, is_active: impl Fn(usize, usize) -> bool) {
+        let mask = (0..self.height)
+            .map(|y| (0..self.width).map(|x| is_active(x, y)).collect())
+            .collect();
+        self.mask = Some(mask);
+        self.count_safe_cells();
+    }
+
+    /// Turns a single cell into a hole
+    fn cut_hole(&mut self, x: usize, y: usize) {
+        let (width, height) = (self.width, self.height);
+        let mask = self
+            .mask
+            .get_or_insert_with(|| vec![vec![true; width].into_boxed_slice(); height].into_boxed_slice());
+        mask[y][x] = false;
+    }
+
+    /// Returns true once mines have been placed on the mine field
+    pub fn is_filled(&self) -> bool {
+        self.field.iter().flatten().any(|cell| cell.is_mine)
+    }
+
+    /// Saves the mine field, including revealed and marked cells, to the given file
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut data = format!(
+            "{} {} {} {} {} {}\n",
+            self.width(),
+            self.height(),
+            self.mine_count,
+            self.wrap as usize,
+            self.adjacency as usize,
+            self.moves
+        );
+        for (y, row) in self.field.iter().enumerate() {
+            for (x, cell) in row.iter().enumerate() {
+                data.push(match (cell.is_mine, cell.is_revealed, cell.mark) {
+                    _ if !self.is_active(x, y) => '#',
+                    (false, true, _) => 'o',
+                    (true, true, _) => 'X',
+                    (false, false, Mark::None) => '.',
+                    (true, false, Mark::None) => '*',
+                    (false, false, Mark::Flag) => 'f',
+                    (true, false, Mark::Flag) => 'F',
+                    (false, false, Mark::Question) => 'q',
+                    (true, false, Mark::Question) => 'Q',
+                });
+            }
+            data.push('\n');
+        }
+        fs::write(path, data)
+    }
+
+    /// Loads a mine field previously written by `save` from the given file
+    pub fn load(path: &Path) -> io::Result<MineField> {
+        let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message);
+        let data = fs::read_to_string(path)?;
+        let mut lines = data.lines();
+
+        // Read the header with the dimensions, mine count, wrapping, adjacency and move count
+        // Saves from before the adjacency was stored use the default king adjacency,
+        // and saves from before the move count was stored start counting again from 0
+        let header: Vec<usize> = lines
+            .next()
+            .ok_or_else(|| invalid("missing header"))?
+            .split_whitespace()
+            .map(|value| value.parse().map_err(|_| invalid("invalid header")))
+            .collect::<io::Result<_>>()?;
+        let (width, height, mine_count, wrap, adjacency, moves) = match header[..] {
+            [width, height, mine_count, wrap] => (width, height, mine_count, wrap, 0, 0),
+            [width, height, mine_count, wrap, adjacency] => (width, height, mine_count, wrap, adjacency, 0),
+            [width, height, mine_count, wrap, adjacency, moves] => (width, height, mine_count, wrap, adjacency, moves),
+            _ => return Err(invalid("invalid header")),
+        };
+        let adjacency = match adjacency {
+            0 => Adjacency::King,
+            1 => Adjacency::Knight,
+            2 => Adjacency::VonNeumann,
+            _ => return Err(invalid("invalid header")),
+        };
+        if wrap > 1 {
+            return Err(invalid("invalid header"));
+        }
+
+        // Check the rows against the header before allocating, so a corrupt header can't ask for a huge board
+        let rows: Vec<&str> = lines.take(height).collect();
+        if rows.len() != height {
+            return Err(invalid("missing row"));
+        }
+        if rows.iter().any(|row| row.chars().count() != width) {
+            return Err(invalid("row has the wrong length"));
+        }
+
+        // Read the cell states row by row
+        // The mines are placed once every hole is cut, so the mine counts skip holes further down
+        let mut mine_field = MineField::new(width, height, mine_count).map_err(|_| invalid("invalid header"))?;
+        mine_field.wrap = wrap == 1;
+        mine_field.adjacency = adjacency;
+        mine_field.moves = moves;
+        let mut mines = Vec::new();
+        for (y, row) in rows.into_iter().enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                let (is_mine, is_revealed, mark) = match c {
+                    '#' => {
+                        mine_field.cut_hole(x, y);
+                        continue;
+                    }
+                    'o' => (false, true, Mark::None),
+                    'X' => (true, true, Mark::None),
+                    '.' => (false, false, Mark::None),
+                    '*' => (true, false, Mark::None),
+                    'f' => (false, false, Mark::Flag),
+                    'F' => (true, false, Mark::Flag),
+                    'q' => (false, false, Mark::Question),
+                    'Q' => (true, false, Mark::Question),
+                    _ => return Err(invalid("invalid cell")),
+                };
+                if is_mine {
+                    mines.push((x, y));
+                }
+                mine_field.field[y][x].is_revealed = is_revealed;
+                mine_field.field[y][x].mark = mark;
+            }
+        }
+
+        // A game saved before the first reveal has no mines placed yet
+        if !mines.is_empty() && mines.len() != mine_count {
+            return Err(invalid("mine count doesn't match the board"));
+        }
+        for (x, y) in mines {
+            mine_field.place_mine(x, y);
+        }
+
+        // Pick up where the saved game left off, which may already be over
+        mine_field.count_safe_cells();
+        let is_lost = mine_field.is_lost();
+        mine_field.update_state(is_lost);
+        Ok(mine_field)
+    }
+
+    /// Enables or disables wrapping the board edges around so opposite edges are adjacent
+    /// Takes effect the next time mines are placed, so it should be set before `fill`
+    pub fn set_wrap(&mut self, wrap: bool) {
+        self.wrap = wrap;
+    }
+
+    /// Sets which surrounding cells count as neighbors
+    /// Takes effect the next time mines are placed, so it should be set before `fill`
+    pub fn set_adjacency(&mut self, adjacency: Adjacency) {
+        self.adjacency = adjacency;
+    }
+
+    /// Sets how mines are spread over the board
+    /// Takes effect the next time mines are placed, so it should be set before `fill`
+    pub fn set_distribution(&mut self, distribution: Distribution) {
+        self.distribution = distribution;
+    }
+
+    /// Enables or disables keeping the neighbors of the first revealed cell free of mines, so it opens
+    /// an empty region whenever the board has room for that
+    /// With safe openings disabled only the first revealed cell itself is guaranteed to be safe
+    /// Same as a safe radius of 1 or 0
+    pub fn set_safe_opening(&mut self, safe_opening: bool) {
+        self.safe_radius = safe_opening as usize;
+    }
+
+    /// Sets how many rings of cells around the first revealed cell are kept free of mines,
+    /// 0 for just the cell itself, 1 for the 3x3 square around it and so on
+    /// The radius shrinks for a board that would have too little room left for its mines
+    pub fn set_safe_radius(&mut self, safe_radius: usize) {
+        self.safe_radius = safe_radius;
+    }
+
+    /// Sets how many cells the first reveal has to open at least, counting the numbers around an empty region
+    /// The mines are placed again until the opening is big enough, giving up after a fixed number of attempts
+    /// Takes effect the next time mines are placed, so it should be set before `fill`
+    pub fn set_min_first_opening(&mut self, min_first_opening: usize) {
+        self.min_first_opening = min_first_opening;
+    }
+
+    /// Enables or disables opening the surroundings of a revealed empty cell
+    /// With flooding disabled every cell has to be revealed on its own
+    pub fn set_flood(&mut self, flood: bool) {
+        self.flood = flood;
+    }
+
+    /// Sets what it takes to win the game
+    pub fn set_win_rule(&mut self, win_rule: WinRule) {
+        self.win_rule = win_rule;
+    }
+
+    /// Enables or disables Unicode box-drawing characters and glyphs when printing the board
+    /// Replaces any theme set before
+    pub fn set_unicode(&mut self, unicode: bool) {
+        self.theme = if unicode { Theme::UNICODE } else { Theme::ASCII };
+    }
+
+    /// Sets the characters used to print the board
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.theme = theme;
+    }
+
+    /// Sets the number of terminal columns each cell is printed in, at least 3 so every coordinate fits
+    pub fn set_cell_width(&mut self, cell_width: usize) {
+        self.cell_width = cell_width.max(3);
+    }
+
+    /// Enables or disables showing the hidden mines when printing the board, for debugging
+    /// Only the display changes, the mines still have to be avoided to win
+    pub fn set_peek(&mut self, peek: bool) {
+        self.peek = peek;
+    }
+
+    /// Enables or disables printing every revealed number together with the flags around it,
+    /// like `3/2` for a 2 with three flagged neighbors, for debugging
+    pub fn set_flag_overlay(&mut self, flag_overlay: bool) {
+        self.flag_overlay = flag_overlay;
+    }
+
+    /// Sets how the columns are labeled when printing the board
+    pub fn set_column_labels(&mut self, column_labels: ColumnLabels) {
+        self.column_labels = column_labels;
+    }
+
+    /// Enables or disables numbering the rows from the bottom, so row 1 is printed last
+    /// Only the labels change, the cells keep their coordinates
+    pub fn set_bottom_origin(&mut self, bottom_origin: bool) {
+        self.bottom_origin = bottom_origin;
+    }
+
+    /// Returns the label the column is printed with
+    pub fn column_label(&self, x: usize) -> String {
+        match self.column_labels {
+            ColumnLabels::Numeric => (x + 1).to_string(),
+            ColumnLabels::Alphabetic => {
+                // Bijective base 26, so Z is followed by AA instead of BA
+                let mut letters = Vec::new();
+                let mut n = x + 1;
+                while n > 0 {
+                    n -= 1;
+                    letters.push((b'A' + (n % 26) as u8) as char);
+                    n /= 26;
+                }
+                letters.iter().rev().collect()
+            }
+        }
+    }
+
+    /// Returns the number the row is printed with
+    pub fn row_label(&self, y: usize) -> usize {
+        if self.bottom_origin { self.height - y } else { y + 1 }
+    }
+
+    /// Returns the label of the cell the way a player would type it, like `3 4` or `C4`
+    pub fn cell_label(&self, x: usize, y: usize) -> String {
+        match self.column_labels {
+            ColumnLabels::Numeric => format!("{} {}", self.column_label(x), self.row_label(y)),
+            ColumnLabels::Alphabetic => format!("{}{}", self.column_label(x), self.row_label(y)),
+        }
+    }
+
+    /// Enables or disables flagging the mines left unflagged once the game is won, so the final board
+    /// shows every mine
+    pub fn set_auto_flag(&mut self, auto_flag: bool) {
+        self.auto_flag = auto_flag;
+    }
+
+    /// Enables or disables chording a revealed number when it is revealed again, so clicking a number
+    /// whose flags are all placed opens its other neighbors
+    pub fn set_auto_chord_on_revealed(&mut self, auto_chord_on_revealed: bool) {
+        self.auto_chord_on_revealed = auto_chord_on_revealed;
+    }
+
+    /// Enables or disables printing the number of mines in each row and column next to the board,
+    /// a training aid that gives away part of the solution
+    pub fn set_margins(&mut self, margins: bool) {
+        self.margins = margins;
+    }
+
+    /// Sets the cell highlighted when printing the board, or `None` to highlight no cell
+    pub fn set_cursor(&mut self, cursor: Option<(usize, usize)>) {
+        self.cursor = cursor;
+    }
+
+    /// Enables or disables colored output when printing the board
+    pub fn set_color(&mut self, use_color: bool) {
+        self.use_color = use_color;
+    }
+
+    /// Returns a copy of the board to try moves on without affecting this one, such as a solver looking ahead
+    /// Nothing is shared, the copy has its own cells, history, clock and random number generator state
+    pub fn fork(&self) -> MineField {
+        self.clone()
+    }
+
+    /// Clears all mines, revealed cells and flags so a new game can be filled
+    pub fn reset(&mut self) {
+        self.zero();
+        self.moves = 0;
+        self.start_time = None;
+    }
+
+    /// Returns the number of reveal and chord actions taken by the player
+    /// A reveal that opens a whole empty region still counts as a single move
+    pub fn moves(&self) -> usize {
+        self.moves
+    }
+
+    /// Returns the time elapsed since the first cell was revealed
+    pub fn elapsed(&self) -> Duration {
+        self.start_time.map_or(Duration::ZERO, |start_time| start_time.elapsed())
+    }
+
+    /// Returns whether the game is still going on or how it ended
+    pub fn state(&self) -> GameState {
+        self.state
+    }
+
+    /// Reveals a cell at the given coordinates, counting it as a move and starting the timer
+    /// Returns the state of the game after the move
+    pub fn reveal(&mut self, x: usize, y: usize) -> Result<GameState, BoardError> {
+        Ok(self.reveal_cells(x, y)?.0)
+    }
+
+    /// Reveals the cell at the given row-major index, like `reveal` does for its coordinates
+    pub fn reveal_index(&mut self, index: usize) -> Result<GameState, BoardError> {
+        let (x, y) = self.index_to_xy(index);
+        self.reveal(x, y)
+    }
+
+    /// Reveals a cell at the given coordinates, counting it as a move and starting the timer
+    /// Returns the state of the game after the move and the coordinates of every newly revealed cell
+    /// Flagged and already revealed cells are left alone without counting a move, unless auto chording
+    /// is enabled and the revealed cell is a number that can be chorded
+    pub fn reveal_cells(&mut self, x: usize, y: usize) -> Result<(GameState, Vec<(usize, usize)>), BoardError> {
+        self.check_playing()?;
+        self.check_bounds(x, y)?;
+        let mut revealed = Vec::new();
+
+        let cell = self.field[y][x];
+        if cell.is_revealed && self.auto_chord_on_revealed {
+            return self.chord_cells(x, y);
+        }
+
+        // Flagged cells are protected from being revealed and revealed cells have nothing left to open,
+        // so trying to doesn't count as a move
+        if cell.is_flagged() || cell.is_revealed {
+            return Ok((self.state, revealed));
+        }
+        self.start_time.get_or_insert_with(Instant::now);
+        self.moves += 1;
+        self.actions.push(Action::Reveal { x, y });
+
+        let hit_mine = self.open(x, y, &mut revealed);
+        debug!("revealed ({}, {}), opening {} cells", x, y, revealed.len());
+        self.history.push(revealed.clone());
+        Ok((self.update_state(hit_mine), revealed))
+    }
+
+    /// Hides the cells revealed by the last reveal or chord again
+    /// Undoing is refused once the game is over
+    /// Returns true if a move was undone
+    pub fn undo(&mut self) -> bool {
+        if self.state != GameState::Playing {
+            return false;
+        }
+
+        match self.history.pop() {
+            Some(revealed) => {
+                for (x, y) in revealed {
+                    let cell = &mut self.field[y][x];
+                    cell.is_revealed = false;
+                    self.revealed_safe -= !cell.is_mine as usize;
+                }
+
+                // Forget the undone move so neither the move count nor a replay includes it
+                self.moves -= 1;
+                if let Some(index) = self.actions.iter().rposition(|action| !matches!(action, Action::Flag { .. })) {
+                    self.actions.remove(index);
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Reveals the given cells in order, stopping early once the game is won or lost
+    /// Returns the state of the game after the last reveal
+    /// Fails on the first coordinates outside the mine field, keeping the reveals made before it
+    pub fn reveal_many(&mut self, coords: &[(usize, usize)]) -> Result<GameState, BoardError> {
+        for &(x, y) in coords {
+            let (state, _) = self.reveal_cells(x, y)?;
+            if state != GameState::Playing {
+                return Ok(state);
+            }
+        }
+        Ok(self.state)
+    }
+
+    /// Reveals a random unrevealed, unflagged cell that holds no mine, peeking at the mines to do so
+    /// Meant for demonstrations, where the board can be watched opening up without ever losing
+    /// Places the mines around the chosen cell first if they haven't been placed yet
+    /// Returns the chosen cell, or `None` if the game is over or no such cell is left
+    pub fn reveal_random_safe<R: Rng + ?Sized>(&mut self, rng: &mut R) -> Option<(usize, usize)> {
+        if self.state != GameState::Playing {
+            return None;
+        }
+
+        let (x, y) = self
+            .cells()
+            .filter(|&(x, y, cell)| {
+                self.is_active(x, y) && !cell.is_revealed && !cell.is_mine && !cell.is_flagged()
+            })
+            .map(|(x, y, _)| (x, y))
+            .choose(rng)?;
+
+        if !self.is_filled() {
+            self.fill(x, y);
+        }
+        self.reveal(x, y).expect("the cell is part of the mine field");
+        Some((x, y))
+    }
+
+    /// Updates the state of the game after a move depending on whether it revealed a mine
+    fn update_state(&mut self, hit_mine: bool) -> GameState {
+        self.state = if hit_mine {
+            GameState::Lost
+        } else if self.is_won() {
+            GameState::Won
+        } else {
+            GameState::Playing
+        };
+        if self.state != GameState::Playing {
+            info!("game {:?} after {} moves", self.state, self.moves);
+        }
+
+        // Flagging the leftover mines only changes how the final board looks
+        if self.state == GameState::Won && self.auto_flag {
+            self.auto_flag_remaining();
+        }
+        self.state
+    }
+
+    /// Flags every unrevealed mine that isn't flagged yet, replacing question marks on them
+    pub fn auto_flag_remaining(&mut self) {
+        for cell in self.field.iter_mut().flatten() {
+            if cell.is_mine && !cell.is_revealed {
+                cell.mark = Mark::Flag;
+            }
+        }
+    }
+
+    /// Returns an error once the game has been won or lost
+    fn check_playing(&self) -> Result<(), BoardError> {
+        match self.state {
+            GameState::Playing => Ok(()),
+            GameState::Won | GameState::Lost => Err(BoardError::GameOver),
+        }
+    }
+
+    /// Reveals a cell at the given coordinates without counting it as a move
+    /// Newly revealed cells are added to `revealed`
+    /// Returns true if a mine was revealed (game over), false otherwise
+    fn open(&mut self, x: usize, y: usize, revealed: &mut Vec<(usize, usize)>) -> bool {
+        let flood = self.flood;
+        let cell = &mut self.field[y][x];
+
+        // Flagged cells are protected from being revealed, revealed cells have already been opened
+        if cell.is_flagged() || cell.is_revealed {
+            return false;
+        }
+        cell.is_revealed = true;
+        revealed.push((x, y));
+
+        // Mine - game over
+        if cell.is_mine {
+            return true;
+        }
+        let is_empty = cell.adjacent_mines == 0;
+        self.revealed_safe += 1;
+
+        // Empty cell - also reveal all adjacent empty cells unless flooding is disabled,
+        // number cells are just revealed
+        if is_empty && flood {
+            self.reveal_adjacent(x, y, revealed);
+        }
+        false
+    }
+
+    /// Returns true once the win rule is met: every non-mine cell has been revealed,
+    /// and under the strict rule every mine and only the mines are flagged as well
+    pub fn is_won(&self) -> bool {
+        match self.win_rule {
+            WinRule::RevealSafe => self.all_safe_revealed(),
+            WinRule::Strict => {
+                self.all_safe_revealed() && self.cells().all(|(_, _, cell)| cell.is_flagged() == cell.is_mine)
+            }
+        }
+    }
+
+    /// Returns true when every non-mine cell has been revealed
+    /// Uses the kept up to date counts, so it doesn't scan the board
+    fn all_safe_revealed(&self) -> bool {
+        self.revealed_safe == self.total_safe
+    }
+
+    /// Recounts the revealed and total non-mine cells by scanning the board
+    /// Needed whenever cells change other than by revealing or undoing
+    fn count_safe_cells(&mut self) {
+        (self.revealed_safe, self.total_safe) = self.safe_cell_counts();
+    }
+
+    /// Returns the number of revealed non-mine cells and the total number of non-mine cells
+    /// Before the mines are placed the total is what will remain once they are
+    fn safe_cell_counts(&self) -> (usize, usize) {
+        let mut revealed = 0;
+        let mut active = 0;
+        let mut mines = 0;
+        for (_, _, cell) in self.cells().filter(|&(x, y, _)| self.is_active(x, y)) {
+            revealed += (cell.is_revealed && !cell.is_mine) as usize;
+            active += 1;
+            mines += cell.is_mine as usize;
+        }
+
+        let mines = if self.is_filled() { mines } else { self.mine_count.min(active) };
+        (revealed, active - mines)
+    }
+
+    /// Ends a game still in progress as lost because the player ran out of time
+    /// Returns the state of the game afterwards
+    pub fn time_out(&mut self) -> GameState {
+        if self.state == GameState::Playing {
+            info!("game Lost on time after {} moves", self.moves);
+            self.state = GameState::Lost;
+            self.timed_out = true;
+        }
+        self.state
+    }
+
+    /// Reveals every mine on the board, used to show the full layout once the game is over
+    pub fn reveal_all_mines(&mut self) {
+        for cell in self.field.iter_mut().flatten() {
+            if cell.is_mine {
+                cell.is_revealed = true;
+            }
+        }
+    }
+
+    /// Returns true when a mine has been revealed
+    pub fn is_lost(&self) -> bool {
+        self.field.iter().flatten().any(|cell| cell.is_revealed && cell.is_mine)
+    }
+
+    /// Returns the number of mines minus the number of placed flags, negative if the player over-flagged
+    /// Question marks are not counted
+    pub fn remaining_mines(&self) -> isize {
+        let flagged = self.field.iter().flatten().filter(|cell| cell.is_flagged()).count();
+        self.mine_count as isize - flagged as isize
+    }
+
+    /// Returns true if more flags are placed than there are mines, so at least one of them is wrong
+    pub fn is_over_flagged(&self) -> bool {
+        self.remaining_mines() < 0
+    }
+
+    /// Returns true if the cell at (x, y) is flagged but holds no mine, false for coordinates off the board
+    pub fn is_wrong_flag(&self, x: usize, y: usize) -> bool {
+        self.check_bounds(x, y).is_ok() && self.field[y][x].is_flagged() && !self.field[y][x].is_mine
+    }
+
+    /// Returns the number of revealed non-mine cells
+    /// Mines uncovered when the game is lost are not counted
+    pub fn revealed_count(&self) -> usize {
+        self.revealed_safe
+    }
+
+    /// Returns the number of cells that are still unrevealed, mines included
+    /// Holes are not counted
+    pub fn hidden_count(&self) -> usize {
+        self.cells().filter(|&(x, y, cell)| self.is_active(x, y) && !cell.is_revealed).count()
+    }
+
+    /// Cycles the mark on an unrevealed cell from none to flag to question mark and back to none
+    /// Revealed cells are left untouched
+    pub fn flag(&mut self, x: usize, y: usize) -> Result<(), BoardError> {
+        self.check_playing()?;
+        self.check_bounds(x, y)?;
+        let cell = &mut self.field[y][x];
+        if !cell.is_revealed {
+            cell.mark = match cell.mark {
+                Mark::None => Mark::Flag,
+                Mark::Flag => Mark::Question,
+                Mark::Question => Mark::None,
+            };
+            self.actions.push(Action::Flag { x, y });
+        }
+
+        // Under the strict win rule placing the last flag can win the game
+        self.update_state(false);
+        Ok(())
+    }
+
+    /// Reveals all unflagged neighbors of a revealed number whose adjacent mines are all flagged
+    /// Counts as a single move when the chord is performed
+    /// Returns the state of the game after the chord, which is lost if a misplaced flag let it reveal a mine
+    pub fn chord(&mut self, x: usize, y: usize) -> Result<GameState, BoardError> {
+        Ok(self.chord_cells(x, y)?.0)
+    }
+
+    /// Chords like `chord`, also returning the coordinates of every newly revealed cell
+    fn chord_cells(&mut self, x: usize, y: usize) -> Result<(GameState, Vec<(usize, usize)>), BoardError> {
+        self.check_playing()?;
+        self.check_bounds(x, y)?;
+        let cell = self.field[y][x];
+        if !cell.is_revealed || cell.is_mine || cell.adjacent_mines == 0 {
+            return Ok((self.state, Vec::new()));
+        }
+
+        // Only chord once the number of flags around the cell matches its number
+        if self.flagged_neighbors(x, y).len() != cell.adjacent_mines as usize {
+            return Ok((self.state, Vec::new()));
+        }
+
+        // The whole chord counts as a single move
+        self.moves += 1;
+        self.actions.push(Action::Chord { x, y });
+        let mut hit_mine = false;
+        let mut revealed = Vec::new();
+        for (x, y) in self.neighbors(x, y) {
+            if !self.field[y][x].is_revealed {
+                hit_mine |= self.open(x, y, &mut revealed);
+            }
+        }
+        debug!("chorded ({}, {}), opening {} cells", x, y, revealed.len());
+        self.history.push(revealed.clone());
+        Ok((self.update_state(hit_mine), revealed))
+    }
+
+    /// Returns every revealed number that can be chorded: its flags match its number and it still has
+    /// unflagged hidden neighbors to open
+    /// The chord is only safe if the flags are right
+    pub fn available_chords(&self) -> Vec<(usize, usize)> {
+        self.cells()
+            .filter(|&(x, y, cell)| {
+                cell.is_revealed
+                    && !cell.is_mine
+                    && cell.adjacent_mines > 0
+                    && self.flagged_neighbors(x, y).len() == cell.adjacent_mines as usize
+                    && self.unrevealed_neighbors(x, y).len() > cell.adjacent_mines as usize
+            })
+            .map(|(x, y, _)| (x, y))
+            .collect()
+    }
+
+    /// Returns the neighbors of the given cell that haven't been revealed, flagged ones included
+    pub fn unrevealed_neighbors(&self, x: usize, y: usize) -> Vec<(usize, usize)> {
+        self.neighbors(x, y).filter(|&(x, y)| !self.field[y][x].is_revealed).collect()
+    }
+
+    /// Returns the neighbors of the given cell that carry a flag
+    pub fn flagged_neighbors(&self, x: usize, y: usize) -> Vec<(usize, usize)> {
+        self.neighbors(x, y).filter(|&(x, y)| self.field[y][x].is_flagged()).collect()
+    }
+
+    /// Returns true if the cell is a revealed number whose unflagged mines outnumber the safe cells
+    /// among its hidden, unflagged neighbors, so guessing next to it is more likely to hit a mine than not
+    /// Coordinates outside the mine field or on a hole are never dangerous
+    pub fn is_dangerous(&self, x: usize, y: usize) -> bool {
+        if self.check_bounds(x, y).is_err() {
+            return false;
+        }
+        let cell = self.field[y][x];
+        if !cell.is_revealed || cell.is_mine || cell.adjacent_mines == 0 {
+            return false;
+        }
+
+        let flagged = self.flagged_neighbors(x, y).len();
+        let unflagged = self.unrevealed_neighbors(x, y).len() - flagged;
+        let mines = (cell.adjacent_mines as usize).saturating_sub(flagged);
+        mines > unflagged.saturating_sub(mines)
+    }
+
+    /// Returns an error if the coordinates lie outside the mine field or on a hole
+    fn check_bounds(&self, x: usize, y: usize) -> Result<(), BoardError> {
+        if x < self.width && y < self.height && self.is_active(x, y) {
+            Ok(())
+        } else {
+            Err(BoardError::OutOfBounds(x, y))
+        }
+    }
+
+    /// Returns the coordinates of all neighbors of the given cell for the board's adjacency
+    /// On a wrapping board the edges connect, so every cell has the full set of neighbors
+    /// Holes are never neighbors
+    fn neighbors(&self, x: usize, y: usize) -> impl Iterator<Item = (usize, usize)> + use<> {
+        let width = self.width as isize;
+        let height = self.height as isize;
+        let mut neighbors = Vec::with_capacity(8);
+
+        for &(dx, dy) in self.adjacency.offsets() {
+            let mut nx = x as isize + dx;
+            let mut ny = y as isize + dy;
+
+            // Wrap around the edges, or skip cells outside of the board
+            if self.wrap {
+                nx = nx.rem_euclid(width);
+                ny = ny.rem_euclid(height);
+            } else if nx < 0 || nx >= width || ny < 0 || ny >= height {
+                continue;
+            }
+
+            // Skip the cell itself, holes, and duplicates when a tiny board wraps onto itself
+            let neighbor = (nx as usize, ny as usize);
+            if neighbor != (x, y) && self.is_active(neighbor.0, neighbor.1) && !neighbors.contains(&neighbor) {
+                neighbors.push(neighbor);
+            }
+        }
+        neighbors.into_iter()
+    }
+
+    /// Reveals adjacent cells when an empty cell is revealed, spreading through connected empty cells
+    /// The numbered cells bordering the region are revealed as a single ring but never spread further,
+    /// so separate empty regions sharing a numbered border stay separate
+    /// Flagged cells keep the player's flag and stay covered, and the flood doesn't pass through them
+    /// even when they are empty, while question marks are opened like unmarked cells
+    /// Uses an explicit work stack instead of recursion so large empty regions can't overflow the stack
+    /// Newly revealed cells are added to `revealed`
+    fn reveal_adjacent(&mut self, x: usize, y: usize, revealed: &mut Vec<(usize, usize)>) {
+        let mut pending = vec![(x, y)];
+
+        while let Some((x, y)) = pending.pop() {
+            // Check all adjacent cells
+            for (dx, dy) in self.neighbors(x, y) {
+                let cell = &mut self.field[dy][dx];
+
+                // Skip revealed cells and flagged cells, which are protected from being revealed
+                // Mines are never adjacent to an empty cell so they can't be reached here
+                if cell.is_revealed || cell.is_flagged() {
+                    continue;
+                }
+                cell.is_revealed = true;
+                revealed.push((dx, dy));
+
+                // Empty cell - queue its neighbors, number cells are just revealed
+                if cell.adjacent_mines == 0 {
+                    pending.push((dx, dy));
+                }
+                self.revealed_safe += 1;
+            }
+        }
+    }
+
+    /// Resets all cells to empty and forgets the move history
+    fn zero(&mut self) {
+        for row in self.field.iter_mut() {
+            row.fill(Cell::default());
+        }
+        self.history.clear();
+        self.actions.clear();
+        self.state = GameState::Playing;
+        self.timed_out = false;
+        self.count_safe_cells();
+    }
+
+    /// Displays the current state of the mine field
+    /// Not available on WebAssembly, where `render` or `to_string` can be used instead
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn print(&self) {
+        self.render(&mut io::stdout()).expect("failed printing to stdout");
+    }
+
+    /// Writes the current state of the mine field to any output, like a file or a buffer
+    pub fn render<W: io::Write>(&self, out: &mut W) -> io::Result<()> {
+        write!(out, "{}", self)
+    }
+
+    /// Displays a banner summarizing how the game ended
+    /// Not available on WebAssembly, where `result_banner` can be used instead
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn print_result(&self, state: GameState) {
+        print!("{}", self.result_banner(state));
+    }
+
+    /// Returns a boxed banner summarizing the game: the time and moves, the board statistics
+    /// for a win and the mine locations for a loss
+    pub fn result_banner(&self, state: GameState) -> String {
+        const MINES_PER_LINE: usize = 8;  // Keep the box narrow on boards with many mines
+
+        let time = format!("Time: {}s   Moves: {}", self.elapsed().as_secs(), self.moves);
+        let (title, color, mut lines) = match state {
+            GameState::Playing => ("GAME IN PROGRESS", "", vec![time]),
+            GameState::Won => {
+                let stats = self.stats();
+                let stats = format!(
+                    "3BV: {}   Efficiency: {:.0}%   3BV/s: {:.2}",
+                    stats.bbbv,
+                    stats.efficiency(),
+                    stats.bbbv_per_second()
+                );
+                ("YOU WON!", COLOR_WIN, vec![time, stats])
+            }
+            GameState::Lost => {
+                let title = if self.timed_out { "GAME OVER! Time's up!" } else { "GAME OVER! You hit a mine!" };
+                (title, COLOR_MINE, vec![time, "Mines at:".to_string()])
+            }
+        };
+
+        // List the mines with the labels the board shows them at
+        if state == GameState::Lost {
+            let mines: Vec<String> = self
+                .cells()
+                .filter(|(_, _, cell)| cell.is_mine)
+                .map(|(x, y, _)| format!("({})", self.cell_label(x, y)))
+                .collect();
+            lines.extend(mines.chunks(MINES_PER_LINE).map(|chunk| chunk.join(" ")));
+        }
+
+        // Center the title and left-align the other lines in a box fitting the longest line
+        let [top_left, top_right, bottom_left, bottom_right, horizontal, vertical] = self.theme.border;
+        let width = lines.iter().map(String::len).chain([title.len()]).max().unwrap_or(0) + 2;
+        let (color, reset) = if self.use_color && !color.is_empty() { (color, COLOR_RESET) } else { ("", "") };
+
+        let mut banner = format!("{}{}{}\n", top_left, horizontal.repeat(width), top_right);
+        banner.push_str(&format!("{}{}{:^width$}{}{}\n", vertical, color, title, reset, vertical));
+        for line in &lines {
+            banner.push_str(&format!("{} {:<pad$}{}\n", vertical, line, vertical, pad = width - 1));
+        }
+        banner.push_str(&format!("{}{}{}\n", bottom_left, horizontal.repeat(width), bottom_right));
+        banner
+    }
+
+    /// Number of terminal columns each cell takes up when printed
+    fn printed_cell_width(&self) -> usize {
+        // The flag overlay needs room for a flag count, a slash and the number with a space in front
+        if self.flag_overlay { self.cell_width.max(4) } else { self.cell_width }
+    }
+
+    /// Number of terminal columns taken up by everything but the cells in each printed row
+    fn printed_frame_width(&self) -> usize {
+        // Row coordinates and borders take up 3 columns on each side, the row margin 4 more
+        if self.margins { 10 } else { 6 }
+    }
+
+    /// Number of terminal columns needed to print the whole board without wrapping
+    pub fn printed_width(&self) -> usize {
+        self.width * self.printed_cell_width() + self.printed_frame_width()
+    }
+
+    /// Returns the number of mines in each row, top to bottom, and in each column, left to right
+    pub fn mine_margins(&self) -> (Vec<usize>, Vec<usize>) {
+        let mut rows = vec![0; self.height];
+        let mut columns = vec![0; self.width];
+        for (x, y, _) in self.cells().filter(|(_, _, cell)| cell.is_mine) {
+            rows[y] += 1;
+            columns[x] += 1;
+        }
+        (rows, columns)
+    }
+
+    /// Splits the columns into panels that each fit into `max_width` terminal columns when printed
+    /// Every panel holds at least one column, even if it doesn't fit
+    pub fn column_panels(&self, max_width: usize) -> Vec<Range<usize>> {
+        let per_panel = (max_width.saturating_sub(self.printed_frame_width()) / self.printed_cell_width()).max(1);
+        (0..self.width)
+            .step_by(per_panel)
+            .map(|start| start..(start + per_panel).min(self.width))
+            .collect()
+    }
+
+    /// Writes the current state of the mine field, splitting the columns into panels that fit into
+    /// `max_width` terminal columns, one below the other
+    pub fn render_paged<W: io::Write>(&self, out: &mut W, max_width: usize) -> io::Result<()> {
+        let mut text = String::new();
+        self.write_header(&mut text).expect("writing to a string cannot fail");
+        for (i, columns) in self.column_panels(max_width).into_iter().enumerate() {
+            if i > 0 {
+                text.push('\n');
+            }
+            self.write_columns(&mut text, columns).expect("writing to a string cannot fail");
+        }
+        write!(out, "{}", text)
+    }
+
+    /// Returns a scaled down overview of the board, one character for every `scale` by `scale` block of cells,
+    /// one row per line
+    /// A block is `#` while all of its cells are hidden, `+` while most are, `-` once most are revealed and
+    /// `.` once all are, blocks made up of holes only are blank
+    pub fn render_minimap(&self, scale: usize) -> String {
+        let scale = scale.max(1);
+        let mut minimap = String::new();
+        for block_y in (0..self.height).step_by(scale) {
+            for block_x in (0..self.width).step_by(scale) {
+                let mut active = 0;
+                let mut revealed = 0;
+                for y in block_y..(block_y + scale).min(self.height) {
+                    for x in block_x..(block_x + scale).min(self.width) {
+                        if self.is_active(x, y) {
+                            active += 1;
+                            revealed += self.field[y][x].is_revealed as usize;
+                        }
+                    }
+                }
+
+                minimap.push(match revealed {
+                    _ if active == 0 => ' ',
+                    0 => '#',
+                    _ if revealed == active => '.',
+                    _ if revealed * 2 > active => '-',
+                    _ => '+',
+                });
+            }
+            minimap.push('\n');
+        }
+        minimap
+    }
+
+    /// Returns the whole board drawn at the top of a cleared terminal screen, the starting point
+    /// for `live_update`
+    pub fn live_frame(&self) -> String {
+        format!("\x1b[H\x1b[2J{}", self)
+    }
+
+    /// Returns the ANSI escape sequences that redraw the header and the given cells of a board drawn
+    /// by `live_frame`, leaving the rest of the screen and the cursor position as they are
+    pub fn live_update(&self, cells: &[(usize, usize)]) -> String {
+        let cell_width = self.printed_cell_width();
+        let is_lost = self.is_lost();
+
+        // Save the cursor, then rewrite the header on the first line
+        let mut update = String::from("\x1b7\x1b[1;1H\x1b[2K");
+        self.write_header(&mut update).expect("writing to a string cannot fail");
+
+        // Flagging changes the overlay of the numbers around the flag as well
+        let mut cells = cells.to_vec();
+        if self.flag_overlay {
+            let neighbors: Vec<(usize, usize)> = cells.iter().flat_map(|&(x, y)| self.neighbors(x, y)).collect();
+            cells.extend(neighbors);
+        }
+
+        // The header, coordinates and top border take up the first 3 lines, the row coordinate and
+        // left border the first 3 columns
+        for (x, y) in cells {
+            write!(update, "\x1b[{};{}H", y + 4, x * cell_width + 4).expect("writing to a string cannot fail");
+            self.write_cell(&mut update, x, y, cell_width, is_lost).expect("writing to a string cannot fail");
+        }
+        update.push_str("\x1b8");
+        update
+    }
+
+    /// Returns the ANSI escape sequences that move the cursor to the line below a board drawn by
+    /// `live_frame` and clear everything after it
+    pub fn live_clear_below(&self) -> String {
+        // The column mine counts take up an extra line under the board
+        let below = self.height + 6 + self.margins as usize;
+        format!("\x1b[{};1H\x1b[J", below)
+    }
+
+    /// Writes the line with the mine counter, moves, elapsed time and progress above the board
+    fn write_header(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        // Print mine counter, moves, elapsed time and progress towards clearing the board
+        let (revealed, total) = (self.revealed_safe, self.total_safe);
+        writeln!(f,
+            "Mines left: {}   Moves: {}   Time: {}s   Revealed: {}% ({}/{} safe cells)",
+            self.remaining_mines(),
+            self.moves,
+            self.elapsed().as_secs(),
+            (revealed * 100).checked_div(total).unwrap_or(100),
+            revealed,
+            total
+        )
+    }
+
+    /// Writes the given columns of the board with their coordinates and borders
+    fn write_columns(&self, f: &mut impl fmt::Write, columns: Range<usize>) -> fmt::Result {
+        let cell_width = self.printed_cell_width();
+
+        // Print top coordinates, right-aligned so each one ends in the same column as the cells below it
+        // Two digits or letters fit in the narrowest cell, which covers every width up to 99
+        write!(f, "    ")?;
+        for x in columns.clone() {
+            write!(f, "{:>1$} ", self.column_label(x), cell_width - 1)?;
+        }
+        writeln!(f)?;
+        
+        let theme = &self.theme;
+        let [top_left, top_right, bottom_left, bottom_right, horizontal, vertical] = theme.border;
+
+        // Once the game is lost, misplaced flags are pointed out
+        let is_lost = self.is_lost();
+
+        // Print top border
+        write!(f, "  {}", top_left)?;
+        for _ in columns.clone() {
+            write!(f, "{}", horizontal.repeat(cell_width))?;
+        }
+        writeln!(f, "{}", top_right)?;
+
+        // Print each row with its y-coordinate, followed by its number of mines if margins are shown
+        let (row_mines, column_mines) = self.mine_margins();
+        for (y, mines) in row_mines.iter().enumerate() {
+            write!(f, "{:2}{}", self.row_label(y), vertical)?;
+            for x in columns.clone() {
+                self.write_cell(f, x, y, cell_width, is_lost)?;
+            }
+            write!(f, "{}{:2}", vertical, self.row_label(y))?;
+            if self.margins {
+                write!(f, " {:>3}", mines)?;
+            }
+            writeln!(f)?;
+        }
+
+        // Print bottom border
+        write!(f, "  {}", bottom_left)?;
+        for _ in columns.clone() {
+            write!(f, "{}", horizontal.repeat(cell_width))?;
+        }
+        writeln!(f, "{}", bottom_right)?;
+
+        // Print bottom coordinates
+        write!(f, "    ")?;
+        for x in columns.clone() {
+            write!(f, "{:>1$} ", self.column_label(x), cell_width - 1)?;
+        }
+        writeln!(f)?;
+
+        // Print the number of mines in each column below its coordinate
+        if self.margins {
+            write!(f, "    ")?;
+            for x in columns {
+                write!(f, "{:>1$} ", column_mines[x], cell_width - 1)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+
+    /// Writes a single cell, right-aligned in `cell_width` columns
+    fn write_cell(&self, f: &mut impl fmt::Write, x: usize, y: usize, cell_width: usize, is_lost: bool) -> fmt::Result {
+        let theme = &self.theme;
+        let overlay;
+        let (symbol, color) = match &self.field[y][x] {
+            _ if !self.is_active(x, y) => (" ", ""),
+            Cell { is_mine: false, mark: Mark::Flag, .. } if is_lost => (theme.wrong_flag, COLOR_MINE),
+            Cell { is_revealed: false, mark: Mark::Flag, .. } => (theme.flag, COLOR_FLAG),
+            Cell { is_revealed: false, mark: Mark::Question, .. } => (theme.question, COLOR_FLAG),
+            Cell { is_revealed: false, is_mine: true, .. } if self.peek => (theme.mine, COLOR_PEEK),
+            Cell { is_revealed: false, .. } => (theme.unrevealed, ""),
+            Cell { is_mine: true, .. } => (theme.mine, COLOR_MINE),
+            Cell { adjacent_mines: 0, .. } => (theme.revealed_empty, ""),
+            Cell { adjacent_mines: n, .. } if self.flag_overlay => {
+                overlay = format!("{}/{}", self.flagged_neighbors(x, y).len(), theme.numbers[*n as usize - 1]);
+                (overlay.as_str(), COLOR_NUMBERS[*n as usize - 1])
+            }
+            Cell { adjacent_mines: n, .. } => {
+                let n = *n as usize - 1;
+                (theme.numbers[n], COLOR_NUMBERS[n])
+            }
+        };
+
+        // Right-align every symbol in the cell, accounting for wide emoji
+        let mut padding = " ".repeat(cell_width.saturating_sub(display_width(symbol)));
+
+        // The cursor is shown in reverse video, or with a marker in front of the symbol without colors
+        let is_cursor = self.cursor == Some((x, y));
+        if is_cursor && self.use_color {
+            write!(f, "{}", COLOR_CURSOR)?;
+        } else if is_cursor && padding.pop().is_some() {
+            padding.push('>');
+        }
+        write!(f, "{}", padding)?;
+
+        // Reset the color after every cell so the terminal state isn't left modified
+        if self.use_color && !color.is_empty() {
+            write!(f, "{}{}{}", color, symbol, COLOR_RESET)?;
+        } else if is_cursor && self.use_color {
+            write!(f, "{}{}", symbol, COLOR_RESET)?;
+        } else {
+            write!(f, "{}", symbol)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for MineField {
+    /// Renders the board the way the player sees it, with the header, coordinates and borders
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.write_header(f)?;
+        self.write_columns(f, 0..self.width)
+    }
+}
+
+impl fmt::Debug for MineField {
+    /// Shows the dimensions and the solved board regardless of what has been revealed
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("MineField")
+            .field("width", &self.width)
+            .field("height", &self.height)
+            .field("mine_count", &self.mine_count)
+            .field("field", &self.to_solution_string().lines().collect::<Vec<_>>())
+            .finish_non_exhaustive()
+    }
+}
+
+impl PartialEq for MineField {
+    /// Boards are equal when they have the same dimensions, mine count, cells and holes
+    /// Settings, the random number generator and the history are ignored
+    fn eq(&self, other: &Self) -> bool {
+        self.width == other.width
+            && self.height == other.height
+            && self.mine_count == other.mine_count
+            && self.field == other.field
+            && self.mask == other.mask
+    }
+}
+
+impl Eq for MineField {}
+
+impl Hash for MineField {
+    /// Hashes the same parts of the board that are compared for equality
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.width.hash(state);
+        self.height.hash(state);
+        self.mine_count.hash(state);
+        self.field.hash(state);
+        self.mask.hash(state);
+    }
+}
+
+impl FromStr for MineField {
+    type Err = ParseError;
+
+    /// Parses a mine layout where `*` is a mine, `.` is an empty cell and `#` is a hole, one row per line
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let rows: Vec<&str> = s.lines().map(str::trim).filter(|row| !row.is_empty()).collect();
+        let width = rows.first().ok_or(ParseError::Empty)?.chars().count();
+
+        // The mines are placed once every hole is cut, so the mine counts skip holes further down
+        let mut mine_field = MineField::new(width, rows.len(), 0).map_err(|_| ParseError::Empty)?;
+        let mut mines = Vec::new();
+        for (y, row) in rows.iter().enumerate() {
+            if row.chars().count() != width {
+                return Err(ParseError::InconsistentRowLength(y));
+            }
+
+            for (x, c) in row.chars().enumerate() {
+                match c {
+                    '*' => mines.push((x, y)),
+                    '.' => {}
+                    '#' => mine_field.cut_hole(x, y),
+                    _ => return Err(ParseError::InvalidCharacter(c)),
+                }
+            }
+        }
+        mine_field.mine_count = mines.len();
+        for (x, y) in mines {
+            mine_field.place_mine(x, y);
+        }
+        mine_field.count_safe_cells();
+        Ok(mine_field)
+    }
+}
+
+/// Picks `mine_count` distinct candidates, favoring the ones close to a few randomly chosen centers
+fn clustered_mines<R: Rng + ?Sized>(candidates: &[(usize, usize)], mine_count: usize, rng: &mut R) -> Vec<(usize, usize)> {
+    const MINES_PER_CLUSTER: usize = 8;  // Roughly how many mines gather around each center
+    const SPREAD: f64 = 4.0;             // How quickly the pull of a center fades with the squared distance
+    const BACKGROUND: f64 = 0.01;        // Weight of cells far from every center, so they can still be picked
+
+    let center_count = mine_count.div_ceil(MINES_PER_CLUSTER).max(1);
+    let centers: Vec<(usize, usize)> = candidates.choose_multiple(rng, center_count).copied().collect();
+
+    let weight = |&(x, y): &(usize, usize)| {
+        let closest = centers
+            .iter()
+            .map(|&(cx, cy)| (x.abs_diff(cx).pow(2) + y.abs_diff(cy).pow(2)) as f64)
+            .fold(f64::INFINITY, f64::min);
+        (-closest / SPREAD).exp() + BACKGROUND
+    };
+    candidates
+        .choose_multiple_weighted(rng, mine_count, weight)
+        .expect("every weight is positive and finite")
+        .copied()
+        .collect()
+}
+
+/// Returns the number of terminal columns a symbol takes up, treating emoji as two columns wide
+fn display_width(symbol: &str) -> usize {
+    symbol.chars().map(|c| if c >= '\u{1F000}' { 2 } else { 1 }).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// Returns a path in the temporary directory that no other test uses
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("minesweeper-{}-{}", std::process::id(), name))
+    }
+
+    #[test]
+    fn is_won_flips_only_on_the_last_safe_reveal() {
+        // Every cell of a 2x2 board touches the mine, so each reveal opens a single cell
+        let mut mine_field = MineField::new(2, 2, 1).unwrap();
+        mine_field.fill(0, 0);
+        let safe: Vec<(usize, usize)> = [(0, 0), (1, 0), (0, 1), (1, 1)]
+            .into_iter()
+            .filter(|&(x, y)| !mine_field.field[y][x].is_mine)
+            .collect();
+        assert_eq!(safe.len(), 3);
+
+        for (i, &(x, y)) in safe.iter().enumerate() {
+            assert!(!mine_field.is_won());
+            assert_ne!(mine_field.reveal(x, y), Ok(GameState::Lost));
+            assert_eq!(mine_field.is_won(), i == safe.len() - 1);
+        }
+    }
+
+    #[test]
+    fn the_first_cell_never_holds_a_mine() {
+        for _ in 0..200 {
+            let mut mine_field = MineField::new(9, 9, 10).unwrap();
+            mine_field.fill(4, 4);
+            for (y, row) in mine_field.field.iter().enumerate().skip(3).take(3) {
+                assert!(row[3..=5].iter().all(|cell| !cell.is_mine), "mine next to (4, 4) in row {}", y);
+            }
+
+            // Without room to spare around it only the cell itself is kept clear
+            let mut mine_field = MineField::new(3, 3, 8).unwrap();
+            mine_field.fill(1, 1);
+            assert!(!mine_field.field[1][1].is_mine);
+        }
+    }
+
+    #[test]
+    fn flood_opens_the_whole_safe_area_of_a_large_board() {
+        let mut layout = vec![".".repeat(99); 99];
+        layout[0].replace_range(0..1, "*");
+        let mut mine_field: MineField = layout.join("\n").parse().unwrap();
+
+        assert_ne!(mine_field.reveal(98, 98), Ok(GameState::Lost));
+        assert!(mine_field.is_won());
+    }
+
+    #[test]
+    fn cell_transitions() {
+        let mut mine_field: MineField = "*.\n..".parse().unwrap();
+        assert_eq!(mine_field.field[0][1], Cell { adjacent_mines: 1, ..Cell::default() });
+
+        // Hidden cells cycle through the marks
+        mine_field.flag(1, 0).unwrap();
+        assert_eq!(mine_field.field[0][1].mark, Mark::Flag);
+        assert!(mine_field.field[0][1].is_flagged());
+
+        // Flagged cells are protected from being revealed
+        assert_ne!(mine_field.reveal(1, 0), Ok(GameState::Lost));
+        assert!(!mine_field.field[0][1].is_revealed);
+
+        mine_field.flag(1, 0).unwrap();
+        assert_eq!(mine_field.field[0][1].mark, Mark::Question);
+        mine_field.flag(1, 0).unwrap();
+        assert_eq!(mine_field.field[0][1].mark, Mark::None);
+
+        // Revealed cells keep their number and can't be marked
+        assert_ne!(mine_field.reveal(1, 0), Ok(GameState::Lost));
+        assert_eq!(mine_field.field[0][1], Cell { is_revealed: true, adjacent_mines: 1, ..Cell::default() });
+        mine_field.flag(1, 0).unwrap();
+        assert_eq!(mine_field.field[0][1].mark, Mark::None);
+
+        // Revealing a mine
+        assert_eq!(mine_field.reveal(0, 0), Ok(GameState::Lost));
+        assert_eq!(mine_field.field[0][0], Cell { is_mine: true, is_revealed: true, ..Cell::default() });
+    }
+
+    #[test]
+    fn expert_preset_is_30_by_16_with_99_mines() {
+        assert_eq!("expert".parse::<Difficulty>().unwrap().settings(), Some((30, 16, 99)));
+        assert_eq!("E".parse::<Difficulty>(), Ok(Difficulty::Expert));
+        assert_eq!(Difficulty::Beginner.settings(), Some((9, 9, 10)));
+        assert_eq!(Difficulty::Custom.settings(), None);
+    }
+
+    #[test]
+    fn same_seed_places_the_same_mines() {
+        let layout = |seed| {
+            let mut mine_field = MineField::new_seeded(16, 16, 40, seed).unwrap();
+            mine_field.fill(3, 3);
+            mine_field.field
+        };
+        assert_eq!(layout(42), layout(42));
+        assert_ne!(layout(42), layout(43));
+    }
+
+    #[test]
+    fn reset_then_fill_starts_a_fresh_game() {
+        let mut mine_field = MineField::new_seeded(9, 9, 10, 5).unwrap();
+        mine_field.fill(4, 4);
+        mine_field.reveal(4, 4).unwrap();
+        mine_field.flag(0, 0).unwrap();
+
+        mine_field.reset();
+        mine_field.fill(4, 4);
+        assert_eq!(mine_field.field.iter().flatten().filter(|cell| cell.is_mine).count(), 10);
+        assert!(mine_field.field.iter().flatten().all(|cell| !cell.is_revealed && cell.mark == Mark::None));
+    }
+
+    #[test]
+    fn chord_with_correct_flags_opens_the_neighbors() {
+        let mut mine_field: MineField = "*..\n...\n..*".parse().unwrap();
+        mine_field.reveal(1, 1).unwrap();
+        mine_field.flag(0, 0).unwrap();
+        mine_field.flag(2, 2).unwrap();
+
+        assert_ne!(mine_field.chord(1, 1), Ok(GameState::Lost));
+        assert!(mine_field.is_won());
+    }
+
+    #[test]
+    fn chord_with_wrong_flags_detonates() {
+        let mut mine_field: MineField = "*..\n...\n..*".parse().unwrap();
+        mine_field.reveal(1, 1).unwrap();
+        mine_field.flag(0, 0).unwrap();
+        mine_field.flag(2, 1).unwrap();
+
+        assert_eq!(mine_field.chord(1, 1), Ok(GameState::Lost));
+        assert!(mine_field.field[2][2].is_revealed);
+    }
+
+    #[test]
+    fn chord_without_enough_flags_does_nothing() {
+        let mut mine_field: MineField = "*..\n...\n..*".parse().unwrap();
+        mine_field.reveal(1, 1).unwrap();
+        mine_field.flag(0, 0).unwrap();
+
+        assert_ne!(mine_field.chord(1, 1), Ok(GameState::Lost));
+        assert_eq!(mine_field.field.iter().flatten().filter(|cell| cell.is_revealed).count(), 1);
+    }
+
+    #[test]
+    fn remaining_mines_counts_down_with_flags() {
+        let mut mine_field = MineField::new_seeded(9, 9, 10, 1).unwrap();
+        for x in 0..3 {
+            mine_field.flag(x, 0).unwrap();
+        }
+        assert_eq!(mine_field.remaining_mines(), 7);
+    }
+
+    #[test]
+    fn parsing_counts_a_center_mine_on_every_neighbor() {
+        let mine_field: MineField = "...\n.*.\n...".parse().unwrap();
+        assert_eq!(mine_field.mine_count, 1);
+        assert!(mine_field.field[1][1].is_mine);
+        let counts: Vec<u8> = mine_field.field.iter().flatten().map(|cell| cell.adjacent_mines).collect();
+        assert_eq!(counts, [1, 1, 1, 1, 0, 1, 1, 1, 1]);
+    }
+
+    #[test]
+    fn layout_string_round_trips_through_parsing() {
+        let layout = "*...\n..*.\n....\n";
+        let mine_field: MineField = layout.parse().unwrap();
+        assert_eq!(mine_field.to_layout_string(), layout);
+        assert_eq!("*.\n...".parse::<MineField>().err(), Some(ParseError::InconsistentRowLength(1)));
+    }
+
+    #[test]
+    fn a_game_in_progress_survives_saving_and_loading() {
+        let mut mine_field = MineField::new_seeded(9, 9, 10, 4).unwrap();
+        mine_field.fill(4, 4);
+        mine_field.reveal(4, 4).unwrap();
+
+        // Flag one mine and one safe cell that are still hidden, and question mark another safe cell
+        let field = &mine_field.field;
+        let hidden = |is_mine| {
+            (0..81).map(|i| (i % 9, i / 9)).filter(move |&(x, y): &(usize, usize)| {
+                !field[y][x].is_revealed && field[y][x].is_mine == is_mine
+            })
+        };
+        let mine = hidden(true).next().unwrap();
+        let safe = hidden(false).next().unwrap();
+        let unsure = hidden(false).next_back().unwrap();
+        mine_field.flag(mine.0, mine.1).unwrap();
+        mine_field.flag(safe.0, safe.1).unwrap();
+        mine_field.flag(unsure.0, unsure.1).unwrap();
+        mine_field.flag(unsure.0, unsure.1).unwrap();
+
+        let path = temp_path("progress.save");
+        mine_field.save(&path).unwrap();
+        let loaded = MineField::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert!(loaded == mine_field);
+        assert_eq!(loaded.state(), GameState::Playing);
+        assert_eq!(loaded.moves(), 1);
+        assert_eq!(loaded.mine_count, 10);
+        assert!(loaded.field[mine.1][mine.0].is_flagged() && loaded.field[safe.1][safe.0].is_flagged());
+        assert_eq!(loaded.field[unsure.1][unsure.0].mark, Mark::Question);
+    }
+
+    #[test]
+    fn marks_cycle_back_and_question_marks_arent_counted() {
+        let mut mine_field = MineField::new_seeded(9, 9, 10, 1).unwrap();
+        mine_field.flag(3, 3).unwrap();
+        assert_eq!(mine_field.remaining_mines(), 9);
+        mine_field.flag(3, 3).unwrap();
+        assert_eq!(mine_field.field[3][3].mark, Mark::Question);
+        assert_eq!(mine_field.remaining_mines(), 10);
+        mine_field.flag(3, 3).unwrap();
+        assert_eq!(mine_field.field[3][3].mark, Mark::None);
+    }
+
+    #[test]
+    fn a_flooding_reveal_counts_as_one_move() {
+        let mut mine_field: MineField = "*....\n.....\n.....\n*****\n.....".parse().unwrap();
+        mine_field.reveal(4, 1).unwrap();
+        assert!(mine_field.field.iter().flatten().filter(|cell| cell.is_revealed).count() > 1);
+        assert_eq!(mine_field.moves(), 1);
+
+        // Flags and trying to reveal a flagged cell aren't moves
+        mine_field.flag(0, 0).unwrap();
+        mine_field.reveal(0, 0).unwrap();
+        assert_eq!(mine_field.moves(), 1);
+    }
+
+    #[test]
+    fn reveal_cells_returns_every_opened_cell() {
+        let mut mine_field: MineField = "......\n......\n......\n.....*".parse().unwrap();
+        let (outcome, mut revealed) = mine_field.reveal_cells(0, 0).unwrap();
+        revealed.sort();
+
+        let mut expected = Vec::new();
+        for (y, row) in mine_field.field.iter().enumerate() {
+            for (x, cell) in row.iter().enumerate() {
+                if cell.is_revealed {
+                    expected.push((x, y));
+                }
+            }
+        }
+        expected.sort();
+        assert_eq!(revealed, expected);
+        assert_eq!(revealed.len(), 23);
+        assert_eq!(outcome, GameState::Won);
+    }
+
+    #[test]
+    fn wrapped_mine_counts_the_opposite_corner() {
+        let mut mine_field = MineField::new(4, 3, 0).unwrap();
+        mine_field.set_wrap(true);
+        mine_field.place_mine(0, 0);
+        assert_eq!(mine_field.field[2][3].adjacent_mines, 1);
+        assert_eq!(mine_field.field[0][3].adjacent_mines, 1);
+        assert_eq!(mine_field.field[2][0].adjacent_mines, 1);
+        assert_eq!(mine_field.field[1][2].adjacent_mines, 0);
+
+        // Corner cells have all 8 neighbors
+        assert_eq!(mine_field.neighbors(0, 0).count(), 8);
+    }
+
+    #[test]
+    fn undo_restores_the_field_before_the_reveal() {
+        let mut mine_field: MineField = "*...\n....\n****\n....".parse().unwrap();
+        mine_field.reveal(1, 0).unwrap();
+        let before = mine_field.field.clone();
+
+        // A flooding reveal is undone as a whole
+        mine_field.reveal(3, 0).unwrap();
+        assert_ne!(mine_field.field, before);
+        assert!(mine_field.undo());
+        assert_eq!(mine_field.field, before);
+
+        mine_field.reveal(0, 0).unwrap();
+        assert!(!mine_field.undo());
+    }
+
+    #[test]
+    fn a_full_board_still_keeps_the_first_cell_safe() {
+        for seed in 0..10 {
+            let mut mine_field = MineField::new_seeded(9, 9, 80, seed).unwrap();
+            mine_field.fill(4, 4);
+            assert_eq!(mine_field.field.iter().flatten().filter(|cell| cell.is_mine).count(), 80);
+            assert_ne!(mine_field.reveal(4, 4), Ok(GameState::Lost));
+            assert!(mine_field.is_won());
+        }
+    }
+
+    #[test]
+    fn cells_are_yielded_row_by_row() {
+        let mine_field = MineField::new(2, 3, 1).unwrap();
+        let coords: Vec<(usize, usize)> = mine_field.cells().map(|(x, y, _)| (x, y)).collect();
+        assert_eq!(coords, [(0, 0), (1, 0), (0, 1), (1, 1), (0, 2), (1, 2)]);
+        assert_eq!((mine_field.width(), mine_field.height()), (2, 3));
+    }
+
+    #[test]
+    fn a_board_without_rows_or_columns_is_rejected() {
+        assert_eq!(MineField::new(0, 5, 0).err(), Some(BoardError::Empty));
+        assert_eq!(MineField::new(5, 0, 0).err(), Some(BoardError::Empty));
+        assert!(MineField::new(1, 1, 0).is_ok());
+    }
+
+    #[test]
+    fn revealing_all_mines_leaves_none_hidden() {
+        let mut mine_field = MineField::new_seeded(9, 9, 10, 2).unwrap();
+        mine_field.fill(4, 4);
+        mine_field.reveal_all_mines();
+        assert!(mine_field.cells().filter(|(_, _, cell)| cell.is_mine).all(|(_, _, cell)| cell.is_revealed));
+        assert!(mine_field.cells().filter(|(_, _, cell)| !cell.is_mine).all(|(_, _, cell)| !cell.is_revealed));
+    }
+
+    #[test]
+    fn a_detonation_shows_every_mine_and_keeps_the_wrong_flags() {
+        let mut mine_field: MineField = "*.*\n...".parse().unwrap();
+        mine_field.flag(1, 1).unwrap();
+        assert_eq!(mine_field.reveal(0, 0), Ok(GameState::Lost));
+        assert!(mine_field.is_lost());
+
+        mine_field.reveal_all_mines();
+        assert!(mine_field.cells().filter(|(_, _, cell)| cell.is_mine).all(|(_, _, cell)| cell.is_revealed));
+        assert!(mine_field.field[1][1].is_flagged() && !mine_field.field[1][1].is_revealed);
+    }
+
+    #[test]
+    fn seeded_solution_string_is_stable() {
+        let mut mine_field = MineField::new_seeded(6, 4, 5, 7).unwrap();
+        mine_field.fill(0, 0);
+        assert_eq!(mine_field.to_solution_string(), "   1*1\n221111\n**21  \n*4*1  \n");
+    }
+
+    #[test]
+    fn dense_fill_places_exactly_the_mine_count() {
+        for mine_count in [1, 50, 91, 98, 99] {
+            let mut mine_field = MineField::new_seeded(10, 10, mine_count, mine_count as u64).unwrap();
+            mine_field.fill(5, 5);
+            assert_eq!(mine_field.cells().filter(|(_, _, cell)| cell.is_mine).count(), mine_count);
+            assert!(!mine_field.field[5][5].is_mine);
+        }
+    }
+
+    #[test]
+    fn near_full_fill_keeps_the_safe_region_clear() {
+        // 91 mines leave exactly the safe cell and its neighbors free
+        for seed in 0..20 {
+            let mut mine_field = MineField::new_seeded(10, 10, 91, seed).unwrap();
+            mine_field.fill(5, 5);
+            for (x, y, cell) in mine_field.cells() {
+                let is_safe = (4..=6).contains(&x) && (4..=6).contains(&y);
+                assert_eq!(cell.is_mine, !is_safe, "seed {} at ({}, {})", seed, x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn reveal_many_stops_once_the_game_is_over() {
+        // Every safe cell touches a mine, so each reveal opens a single cell
+        let layout = "*.*\n.*.";
+        let mut mine_field: MineField = layout.parse().unwrap();
+        assert_eq!(mine_field.reveal_many(&[(1, 0), (0, 1), (2, 1), (0, 0)]).unwrap(), GameState::Won);
+        assert_eq!(mine_field.moves(), 3);
+        assert!(!mine_field.field[0][0].is_revealed);
+
+        let mut mine_field: MineField = layout.parse().unwrap();
+        assert_eq!(mine_field.reveal_many(&[(1, 0), (0, 0), (2, 1)]).unwrap(), GameState::Lost);
+        assert_eq!(mine_field.moves(), 2);
+        assert!(!mine_field.field[1][2].is_revealed);
+
+        let mut mine_field: MineField = layout.parse().unwrap();
+        assert_eq!(mine_field.reveal_many(&[(1, 0), (0, 1)]).unwrap(), GameState::Playing);
+    }
+
+    #[test]
+    fn revealing_outside_the_board_is_an_error() {
+        let mut mine_field = MineField::new(4, 3, 2).unwrap();
+        assert_eq!(mine_field.reveal(4, 3), Err(BoardError::OutOfBounds(4, 3)));
+        assert_eq!(mine_field.reveal(4, 0), Err(BoardError::OutOfBounds(4, 0)));
+        assert_eq!(mine_field.reveal(0, 3), Err(BoardError::OutOfBounds(0, 3)));
+        assert_eq!(mine_field.moves(), 0);
+        assert!(mine_field.reveal(3, 2).is_ok());
+    }
+
+    #[test]
+    fn a_cross_shaped_board_is_won_by_clearing_its_active_cells() {
+        for seed in 0..20 {
+            let mut mine_field = MineField::new_seeded(5, 5, 3, seed).unwrap();
+            mine_field.set_mask(|x, y| x == 2 || y == 2);
+            mine_field.fill(2, 2);
+
+            // Mines only land in the arms of the cross
+            let mines: Vec<(usize, usize)> =
+                mine_field.cells().filter(|(_, _, cell)| cell.is_mine).map(|(x, y, _)| (x, y)).collect();
+            assert_eq!(mines.len(), 3);
+            assert!(mines.iter().all(|&(x, y)| mine_field.is_active(x, y)), "seed {}", seed);
+
+            let safe: Vec<(usize, usize)> = mine_field
+                .cells()
+                .filter(|&(x, y, cell)| mine_field.is_active(x, y) && !cell.is_mine)
+                .map(|(x, y, _)| (x, y))
+                .collect();
+            for (x, y) in safe {
+                if !mine_field.field[y][x].is_revealed {
+                    assert!(!mine_field.is_won());
+                    assert_ne!(mine_field.reveal(x, y), Ok(GameState::Lost));
+                }
+            }
+            assert!(mine_field.is_won(), "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn load_counts_mines_around_holes_after_them() {
+        let mut mine_field = MineField::new_seeded(5, 5, 5, 0).unwrap();
+        mine_field.set_mask(|x, y| (x * 3 + y) % 4 != 1);
+        mine_field.fill(0, 0);
+
+        let path = temp_path("masked.save");
+        mine_field.save(&path).unwrap();
+        let loaded = MineField::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert!(loaded == mine_field);
+        assert!(loaded.validate().is_ok());
+    }
+
+    #[test]
+    fn parsing_counts_mines_around_holes_after_them() {
+        let mine_field: MineField = "*#\n#.".parse().unwrap();
+        assert_eq!(mine_field.field[0][1].adjacent_mines, 0);
+        assert_eq!(mine_field.field[1][0].adjacent_mines, 0);
+        assert_eq!(mine_field.field[1][1].adjacent_mines, 1);
+        assert!(mine_field.validate().is_ok());
+    }
+
+    /// Returns a small board with a flag and two numbers revealed, printed without colors
+    fn sample_board() -> MineField {
+        let mut mine_field: MineField = "*.*\n.*.".parse().unwrap();
+        mine_field.set_color(false);
+        mine_field.flag(0, 0).unwrap();
+        mine_field.reveal(1, 0).unwrap();
+        mine_field.reveal(0, 1).unwrap();
+        mine_field
+    }
+
+    #[test]
+    fn display_matches_the_printed_board() {
+        let expected = [
+            "Mines left: 2   Moves: 2   Time: 0s   Revealed: 66% (2/3 safe cells)",
+            "     1  2  3 ",
+            "  +---------+",
+            " 1|  F  3   | 1",
+            " 2|  2      | 2",
+            "  +---------+",
+            "     1  2  3 ",
+        ];
+        assert_eq!(sample_board().to_string(), expected.join("\n") + "\n");
+    }
+
+    #[test]
+    fn unicode_rendering() {
+        let mut mine_field = sample_board();
+        mine_field.set_unicode(true);
+        let expected = [
+            "Mines left: 2   Moves: 2   Time: 0s   Revealed: 66% (2/3 safe cells)",
+            "     1  2  3 ",
+            "  ┌─────────┐",
+            " 1│  ⚑  3   │ 1",
+            " 2│  2      │ 2",
+            "  └─────────┘",
+            "     1  2  3 ",
+        ];
+        assert_eq!(mine_field.to_string(), expected.join("\n") + "\n");
+    }
+
+    #[test]
+    fn render_writes_the_board_to_any_output() {
+        let mine_field = sample_board();
+        let mut out = Vec::new();
+        mine_field.render(&mut out).unwrap();
+        assert_eq!(out, mine_field.to_string().into_bytes());
+        assert!(out.starts_with(b"Mines left: 2   Moves: 2"));
+        assert!(out.ends_with(b"     1  2  3 \n"));
+    }
+
+    /// Returns the cells counting a single mine in the center of a 5x5 board under the given adjacency
+    fn cells_counting_center_mine(adjacency: Adjacency) -> Vec<(usize, usize)> {
+        let mut mine_field = MineField::new(5, 5, 1).unwrap();
+        mine_field.set_adjacency(adjacency);
+        mine_field.place_mine(2, 2);
+        mine_field.cells().filter(|(_, _, cell)| cell.adjacent_mines == 1).map(|(x, y, _)| (x, y)).collect()
+    }
+
+    #[test]
+    fn each_adjacency_counts_its_own_neighbors() {
+        assert_eq!(
+            cells_counting_center_mine(Adjacency::King),
+            [(1, 1), (2, 1), (3, 1), (1, 2), (3, 2), (1, 3), (2, 3), (3, 3)]
+        );
+        assert_eq!(
+            cells_counting_center_mine(Adjacency::Knight),
+            [(1, 0), (3, 0), (0, 1), (4, 1), (0, 3), (4, 3), (1, 4), (3, 4)]
+        );
+        assert_eq!(cells_counting_center_mine(Adjacency::VonNeumann), [(2, 1), (1, 2), (3, 2), (2, 3)]);
+    }
+
+    #[test]
+    fn knight_flood_follows_knight_moves() {
+        let mut mine_field = MineField::new(4, 4, 1).unwrap();
+        mine_field.set_adjacency(Adjacency::Knight);
+        mine_field.place_mine(3, 3);
+
+        // (0, 0) and its knight neighbors don't see the mine, but (1, 2) and (2, 1) do
+        let (_, revealed) = mine_field.reveal_cells(0, 0).unwrap();
+        assert!(revealed.contains(&(1, 2)) && revealed.contains(&(2, 1)));
+        assert_eq!(mine_field.field[1][2].adjacent_mines, 1);
+    }
+
+    #[test]
+    fn an_orthogonal_mine_counts_only_on_its_four_sides() {
+        let mut mine_field = MineField::new(3, 3, 1).unwrap();
+        mine_field.set_adjacency(Adjacency::VonNeumann);
+        mine_field.place_mine(1, 1);
+        let counts: Vec<u8> = mine_field.cells().map(|(_, _, cell)| cell.adjacent_mines).collect();
+        assert_eq!(counts, [0, 1, 0, 1, 0, 1, 0, 1, 0]);
+
+        // The diagonal corners are empty, but the flood from one doesn't reach the others
+        let (_, revealed) = mine_field.reveal_cells(0, 0).unwrap();
+        assert_eq!(revealed, [(0, 0), (1, 0), (0, 1)]);
+    }
+
+    #[test]
+    fn the_game_state_follows_the_reveals() {
+        let mut mine_field: MineField = "*.\n..".parse().unwrap();
+        assert_eq!(mine_field.state(), GameState::Playing);
+        assert_eq!(mine_field.reveal(1, 0), Ok(GameState::Playing));
+        assert_eq!(mine_field.reveal(0, 1), Ok(GameState::Playing));
+        assert_eq!(mine_field.reveal(1, 1), Ok(GameState::Won));
+        assert_eq!(mine_field.state(), GameState::Won);
+        assert_eq!(mine_field.reveal(0, 0), Err(BoardError::GameOver));
+
+        let mut mine_field: MineField = "*.\n..".parse().unwrap();
+        assert_eq!(mine_field.reveal(0, 0), Ok(GameState::Lost));
+        assert_eq!(mine_field.state(), GameState::Lost);
+        assert_eq!(mine_field.reveal(1, 0), Err(BoardError::GameOver));
+        assert_eq!(mine_field.flag(1, 1), Err(BoardError::GameOver));
+        assert_eq!(mine_field.chord(1, 0), Err(BoardError::GameOver));
+        assert_eq!(mine_field.moves(), 1);
+    }
+
+    #[test]
+    fn strict_wins_need_every_mine_flagged() {
+        let mut mine_field: MineField = "*.\n..".parse().unwrap();
+        mine_field.set_win_rule(WinRule::Strict);
+        mine_field.reveal_many(&[(1, 0), (0, 1)]).unwrap();
+        assert_eq!(mine_field.reveal(1, 1), Ok(GameState::Playing));
+        assert!(!mine_field.is_won());
+
+        // The same board counts as won under the default rule
+        mine_field.set_win_rule(WinRule::RevealSafe);
+        assert!(mine_field.is_won());
+        mine_field.set_win_rule(WinRule::Strict);
+
+        mine_field.flag(0, 0).unwrap();
+        assert!(mine_field.is_won());
+        assert_eq!(mine_field.state(), GameState::Won);
+    }
+
+    #[test]
+    fn without_flooding_a_zero_opens_only_itself() {
+        let mut mine_field: MineField = "*...\n....\n....".parse().unwrap();
+        mine_field.set_flood(false);
+        let (state, revealed) = mine_field.reveal_cells(3, 2).unwrap();
+        assert_eq!((state, revealed), (GameState::Playing, vec![(3, 2)]));
+        assert_eq!(mine_field.cells().filter(|(_, _, cell)| cell.is_revealed).count(), 1);
+        assert!(!mine_field.field[1][2].is_revealed);
+    }
+
+    #[test]
+    fn revealing_a_revealed_number_again_does_nothing() {
+        let mut mine_field: MineField = "*...\n....\n....".parse().unwrap();
+        mine_field.reveal(1, 1).unwrap();
+        let before = mine_field.to_layout_string() + &mine_field.to_string();
+
+        assert_eq!(mine_field.reveal_cells(1, 1), Ok((GameState::Playing, Vec::new())));
+        assert_eq!(mine_field.moves(), 1);
+        assert_eq!(mine_field.cells().filter(|(_, _, cell)| cell.is_revealed).count(), 1);
+        assert_eq!(mine_field.to_layout_string() + &mine_field.to_string(), before);
+
+        // Only the first reveal is on the history, so a single undo hides the number again
+        assert!(mine_field.undo());
+        assert!(!mine_field.undo());
+    }
+
+    #[test]
+    fn wider_cells_keep_the_board_aligned() {
+        let mut mine_field = sample_board();
+        mine_field.set_cell_width(4);
+        let expected = [
+            "Mines left: 2   Moves: 2   Time: 0s   Revealed: 66% (2/3 safe cells)",
+            "      1   2   3 ",
+            "  +------------+",
+            " 1|   F   3    | 1",
+            " 2|   2        | 2",
+            "  +------------+",
+            "      1   2   3 ",
+        ];
+        assert_eq!(mine_field.to_string(), expected.join("\n") + "\n");
+
+        mine_field.set_cell_width(5);
+        let expected = [
+            "Mines left: 2   Moves: 2   Time: 0s   Revealed: 66% (2/3 safe cells)",
+            "       1    2    3 ",
+            "  +---------------+",
+            " 1|    F    3     | 1",
+            " 2|    2          | 2",
+            "  +---------------+",
+            "       1    2    3 ",
+        ];
+        assert_eq!(mine_field.to_string(), expected.join("\n") + "\n");
+
+        // Widths too narrow for the coordinates fall back to the default
+        mine_field.set_cell_width(2);
+        assert_eq!(mine_field.to_string(), sample_board().to_string());
+    }
+
+    #[test]
+    fn a_safe_opening_starts_on_an_empty_cell() {
+        for seed in 0..50 {
+            let (x, y) = (seed as usize % 9, 4);
+            let mut mine_field = MineField::new_seeded(9, 9, 10, seed).unwrap();
+            mine_field.set_safe_opening(true);
+            mine_field.fill(x, y);
+            mine_field.reveal(x, y).unwrap();
+            let cell = mine_field.field[y][x];
+            assert!(cell.is_revealed && cell.adjacent_mines == 0, "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn result_banner_names_the_outcome() {
+        let board = || {
+            let mut mine_field: MineField = "*.\n..".parse().unwrap();
+            mine_field.set_color(false);
+            mine_field
+        };
+        let mut mine_field = board();
+        assert!(mine_field.result_banner(GameState::Playing).contains("GAME IN PROGRESS"));
+
+        let mut lost = board();
+        lost.reveal(0, 0).unwrap();
+        let banner = lost.result_banner(GameState::Lost);
+        assert!(banner.contains("GAME OVER! You hit a mine!"));
+        assert!(banner.contains("Mines at:") && banner.contains("(1 1)"));
+        assert!(!banner.contains('\x1b'));
+
+        mine_field.reveal_many(&[(1, 0), (0, 1), (1, 1)]).unwrap();
+        let banner = mine_field.result_banner(GameState::Won);
+        assert!(banner.contains("YOU WON!"));
+        assert!(banner.contains("Moves: 3"));
+        assert!(banner.contains("3BV: 3"));
+    }
+
+    #[test]
+    fn zero_regions_sharing_a_number_open_separately() {
+        // The 2 in the center borders both empty corners, which don't touch each other
+        let mut mine_field: MineField = "..*\n...\n*..".parse().unwrap();
+        let (state, mut revealed) = mine_field.reveal_cells(0, 0).unwrap();
+        revealed.sort();
+        assert_eq!((state, revealed), (GameState::Playing, vec![(0, 0), (0, 1), (1, 0), (1, 1)]));
+        assert!(!mine_field.field[2][2].is_revealed);
+
+        let (state, mut revealed) = mine_field.reveal_cells(2, 2).unwrap();
+        revealed.sort();
+        assert_eq!((state, revealed), (GameState::Won, vec![(1, 2), (2, 1), (2, 2)]));
+    }
+
+    #[test]
+    fn peeking_shows_mines_without_changing_the_game() {
+        let layout = "*..\n...";
+        let mut mine_field: MineField = layout.parse().unwrap();
+        mine_field.set_color(false);
+        mine_field.set_flood(false);
+        let mut peeking: MineField = layout.parse().unwrap();
+        peeking.set_color(false);
+        peeking.set_flood(false);
+        peeking.set_peek(true);
+
+        assert!(peeking.to_string().contains(" 1|  *      | 1\n"));
+        assert!(!mine_field.to_string().contains('*'));
+
+        for board in [&mut mine_field, &mut peeking] {
+            assert_eq!(board.reveal(2, 1), Ok(GameState::Playing));
+            assert!(!board.field[0][0].is_revealed);
+            assert!(!board.is_won() && !board.is_lost());
+        }
+        assert_eq!(peeking.field, mine_field.field);
+        assert_eq!(peeking.reveal(0, 0), Ok(GameState::Lost));
+    }
+
+    #[test]
+    fn a_custom_theme_renders_its_glyphs() {
+        let mut mine_field: MineField = "*..\n..*".parse().unwrap();
+        mine_field.set_color(false);
+        mine_field.set_flood(false);
+        mine_field.set_theme(Theme {
+            flag: "!",
+            unrevealed: "~",
+            numbers: ["a", "b", "c", "d", "e", "f", "g", "h"],
+            border: ["/", "\\", "\\", "/", "=", "I"],
+            ..Theme::ASCII
+        });
+        mine_field.reveal(1, 0).unwrap();
+        mine_field.reveal(0, 1).unwrap();
+        mine_field.flag(0, 0).unwrap();
+
+        let rendered = mine_field.to_string();
+        assert!(rendered.contains("  /=========\\\n"));
+        assert!(rendered.contains(" 1I  !  b  ~I 1\n"));
+        assert!(rendered.contains(" 2I  a  ~  ~I 2\n"));
+        assert!(rendered.contains("  \\=========/\n"));
+    }
+
+    #[test]
+    fn the_header_shows_the_share_of_safe_cells_revealed() {
+        let mut mine_field: MineField = "*..\n...".parse().unwrap();
+        let header = |mine_field: &MineField| mine_field.to_string().lines().next().unwrap().to_string();
+        assert!(header(&mine_field).ends_with("Revealed: 0% (0/5 safe cells)"));
+
+        mine_field.reveal(2, 1).unwrap();
+        assert!(header(&mine_field).ends_with("Revealed: 80% (4/5 safe cells)"));
+
+        mine_field.reveal(0, 1).unwrap();
+        assert!(mine_field.is_won());
+        assert!(header(&mine_field).ends_with("Revealed: 100% (5/5 safe cells)"));
+    }
+
+    #[test]
+    fn batches_are_reproducible_from_the_base_seed() {
+        let layouts = |batch: &[MineField]| batch.iter().map(MineField::to_layout_string).collect::<Vec<_>>();
+        let batch = MineField::generate_batch(8, 6, 9, 5, 40).unwrap();
+        let again = MineField::generate_batch(8, 6, 9, 5, 40).unwrap();
+        assert_eq!(batch.len(), 5);
+        assert_eq!(layouts(&batch), layouts(&again));
+
+        // Every board gets its own seed and the full mine count, away from the center cell
+        for (i, mine_field) in batch.iter().enumerate() {
+            let single = MineField::generate_batch(8, 6, 9, 1, 40 + i as u64).unwrap();
+            assert_eq!(mine_field.to_layout_string(), single[0].to_layout_string());
+            assert_eq!(mine_field.cells().filter(|(_, _, cell)| cell.is_mine).count(), 9);
+            assert!(!mine_field.field[3][4].is_mine);
+        }
+        assert_ne!(batch[0].to_layout_string(), batch[1].to_layout_string());
+        assert!(matches!(MineField::generate_batch(0, 6, 9, 2, 0), Err(BoardError::Empty)));
+    }
+
+    #[test]
+    fn the_flag_overlay_counts_flags_around_each_number() {
+        let mut mine_field: MineField = "*.*\n...".parse().unwrap();
+        mine_field.set_color(false);
+        mine_field.set_flood(false);
+        mine_field.set_flag_overlay(true);
+        mine_field.reveal(1, 0).unwrap();
+        mine_field.reveal(1, 1).unwrap();
+        mine_field.flag(0, 0).unwrap();
+        let rendered = mine_field.to_string();
+        assert!(rendered.contains(" 1|   F 1/2    | 1\n"));
+        assert!(rendered.contains(" 2|     1/2    | 2\n"));
+
+        // Over-flagging shows up as more flags than the number asks for
+        mine_field.flag(2, 0).unwrap();
+        mine_field.flag(0, 1).unwrap();
+        let rendered = mine_field.to_string();
+        assert!(rendered.contains(" 1|   F 3/2   F| 1\n"));
+        assert!(rendered.contains(" 2|   F 3/2    | 2\n"));
+        assert_eq!(mine_field.flagged_neighbors(1, 1).len(), 3);
+    }
+
+    #[test]
+    fn recomputed_counts_match_the_filled_board() {
+        for wrap in [false, true] {
+            let mut mine_field = MineField::new_seeded(12, 7, 20, 9).unwrap();
+            mine_field.set_wrap(wrap);
+            mine_field.fill(3, 3);
+            let solution = mine_field.to_solution_string();
+
+            for cell in mine_field.field.iter_mut().flatten() {
+                cell.adjacent_mines = 7;
+            }
+            mine_field.recompute_adjacency();
+            assert_eq!(mine_field.to_solution_string(), solution);
+        }
+    }
+
+    #[test]
+    fn a_density_sets_the_share_of_mines() {
+        let mine_field = MineField::from_density(10, 10, 0.20).unwrap();
+        assert_eq!(mine_field.mine_count(), 20);
+
+        for density in [0.0, 1.0, -0.5, 1.5, f64::NAN] {
+            assert_eq!(MineField::density_mine_count(10, 10, density), Err(BoardError::InvalidDensity));
+            assert_eq!(MineField::from_density(10, 10, density).err(), Some(BoardError::InvalidDensity));
+        }
+
+        // At least one mine and one safe cell, even on a tiny board
+        assert_eq!(MineField::density_mine_count(3, 3, 0.01), Ok(1));
+        assert_eq!(MineField::density_mine_count(3, 3, 0.99), Ok(8));
+    }
+
+    #[test]
+    fn a_wide_board_is_split_into_panels_that_fit() {
+        let mut mine_field = MineField::new(60, 4, 10).unwrap();
+        mine_field.set_color(false);
+        assert_eq!(mine_field.column_panels(80), [0..24, 24..48, 48..60]);
+        assert_eq!(mine_field.column_panels(200).len(), 1);
+        assert_eq!(mine_field.column_panels(5).len(), 60);
+
+        let mut out = Vec::new();
+        mine_field.render_paged(&mut out, 80).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert!(text.lines().skip(1).all(|line| line.len() <= 80));
+        assert_eq!(text.lines().filter(|line| line.starts_with(" 1|")).count(), 3);
+    }
+
+    #[test]
+    fn counts_after_a_flood() {
+        let mut mine_field: MineField = "*...\n....\n....".parse().unwrap();
+        assert_eq!((mine_field.revealed_count(), mine_field.hidden_count()), (0, 12));
+        mine_field.reveal(3, 2).unwrap();
+        assert_eq!((mine_field.revealed_count(), mine_field.hidden_count()), (11, 1));
+
+        // Holes count as neither, a revealed mine only as no longer hidden
+        let mut mine_field: MineField = "*..#\n....\n....".parse().unwrap();
+        mine_field.set_flood(false);
+        mine_field.reveal(2, 2).unwrap();
+        assert_eq!((mine_field.revealed_count(), mine_field.hidden_count()), (1, 10));
+        mine_field.reveal(0, 0).unwrap();
+        assert_eq!((mine_field.revealed_count(), mine_field.hidden_count()), (1, 9));
+    }
+
+    #[test]
+    fn random_safe_reveals_win_without_hitting_a_mine() {
+        let mut rng = StdRng::seed_from_u64(4);
+        for seed in 0..5 {
+            let mut mine_field = MineField::new_seeded(9, 9, 10, seed).unwrap();
+            mine_field.set_flood(seed % 2 == 0);
+            while let Some((x, y)) = mine_field.reveal_random_safe(&mut rng) {
+                assert!(!mine_field.field[y][x].is_mine);
+                assert_ne!(mine_field.state(), GameState::Lost);
+            }
+            assert_eq!(mine_field.state(), GameState::Won);
+            assert_eq!(mine_field.cells().filter(|(_, _, cell)| cell.is_mine).count(), 10);
+        }
+    }
+
+    #[test]
+    fn auto_flag_flags_every_mine_after_a_win() {
+        let mut mine_field: MineField = "*..\n...\n..*".parse().unwrap();
+        mine_field.set_flood(false);
+        mine_field.set_auto_flag(true);
+
+        // A question mark on a mine is replaced by a flag as well
+        mine_field.flag(2, 2).unwrap();
+        mine_field.flag(2, 2).unwrap();
+        mine_field.reveal_many(&[(1, 0), (2, 0), (0, 1), (1, 1), (2, 1), (0, 2)]).unwrap();
+        assert_eq!(mine_field.field[0][0].mark, Mark::None);
+
+        assert_eq!(mine_field.reveal(1, 2), Ok(GameState::Won));
+        assert_eq!(mine_field.field[0][0].mark, Mark::Flag);
+        assert_eq!(mine_field.field[2][2].mark, Mark::Flag);
+        assert_eq!(mine_field.remaining_mines(), 0);
+    }
+
+    #[test]
+    fn validate_reports_cells_with_a_wrong_count() {
+        let mut mine_field = MineField::new_seeded(6, 5, 7, 8).unwrap();
+        mine_field.fill(2, 2);
+        assert_eq!(mine_field.validate(), Ok(()));
+
+        let wrong = mine_field.cells().find(|(_, _, cell)| !cell.is_mine).map(|(x, y, _)| (x, y)).unwrap();
+        mine_field.field[wrong.1][wrong.0].adjacent_mines += 1;
+        mine_field.field[4][5].is_mine = !mine_field.field[4][5].is_mine;
+        let inconsistent = mine_field.validate().unwrap_err();
+        assert!(inconsistent.contains(&wrong));
+        assert!(inconsistent.iter().any(|&(x, y)| x >= 4 && y >= 3 && (x, y) != (5, 4)));
+
+        mine_field.recompute_adjacency();
+        assert_eq!(mine_field.validate(), Ok(()));
+    }
+
+    /// Returns the mean distance from every mine to the nearest other mine
+    fn mean_nearest_mine_distance(mine_field: &MineField) -> f64 {
+        let mines: Vec<(f64, f64)> =
+            mine_field.cells().filter(|(_, _, cell)| cell.is_mine).map(|(x, y, _)| (x as f64, y as f64)).collect();
+        let nearest = |&(x, y): &(f64, f64)| {
+            mines
+                .iter()
+                .filter(|&&other| other != (x, y))
+                .map(|&(ox, oy)| ((ox - x).powi(2) + (oy - y).powi(2)).sqrt())
+                .fold(f64::INFINITY, f64::min)
+        };
+        mines.iter().map(nearest).sum::<f64>() / mines.len() as f64
+    }
+
+    #[test]
+    fn clustered_mines_lie_closer_together_than_uniform_ones() {
+        let (mut clustered, mut uniform) = (0.0, 0.0);
+        for seed in 0..20 {
+            for (distribution, total) in [(Distribution::Clustered, &mut clustered), (Distribution::Uniform, &mut uniform)] {
+                let mut mine_field = MineField::new_seeded(30, 16, 40, seed).unwrap();
+                mine_field.set_distribution(distribution);
+                mine_field.fill(15, 8);
+                assert_eq!(mine_field.cells().filter(|(_, _, cell)| cell.is_mine).count(), 40);
+                assert!(mine_field.validate().is_ok());
+                *total += mean_nearest_mine_distance(&mine_field);
+            }
+        }
+        assert!(clustered < uniform * 0.9, "clustered {} uniform {}", clustered / 20.0, uniform / 20.0);
+    }
+
+    #[test]
+    fn a_seeded_generator_places_the_same_mines() {
+        let mut mine_field = MineField::new(5, 4, 4).unwrap();
+        mine_field.fill_with_rng(0, 0, &mut StdRng::seed_from_u64(21));
+        assert_eq!(mine_field.to_layout_string(), ".....\n...**\n..*..\n....*\n");
+
+        // The generator the board was created with isn't used
+        let mut other = MineField::new(5, 4, 4).unwrap();
+        other.fill_with_rng(0, 0, &mut StdRng::seed_from_u64(21));
+        assert_eq!(other.to_layout_string(), mine_field.to_layout_string());
+    }
+
+    #[test]
+    fn flags_on_the_edge_of_an_opening_stay_put() {
+        let mut mine_field: MineField = "*...\n....\n....".parse().unwrap();
+        mine_field.flag(3, 0).unwrap();
+        mine_field.flag(1, 1).unwrap();
+
+        let (_, revealed) = mine_field.reveal_cells(3, 2).unwrap();
+        assert_eq!(revealed.len(), 9);
+        assert!(!revealed.contains(&(3, 0)) && !revealed.contains(&(1, 1)));
+        assert!(mine_field.field[0][3].is_flagged() && !mine_field.field[0][3].is_revealed);
+        assert!(mine_field.field[1][1].is_flagged() && !mine_field.field[1][1].is_revealed);
+        assert!(mine_field.field[0][2].is_revealed);
+    }
+
+    #[test]
+    fn live_update_redraws_the_header_and_the_given_cells() {
+        let mut mine_field: MineField = "*..\n...".parse().unwrap();
+        mine_field.set_color(false);
+        mine_field.set_flood(false);
+        mine_field.reveal(1, 0).unwrap();
+
+        // Every cell is addressed by its row below the 3 header lines and its column past the left border
+        let header = "Mines left: 1   Moves: 1   Time: 0s   Revealed: 20% (1/5 safe cells)\n";
+        let expected = format!("\x1b7\x1b[1;1H\x1b[2K{}\x1b[4;7H  1\x1b[5;10H   \x1b8", header);
+        assert_eq!(mine_field.live_update(&[(1, 0), (2, 1)]), expected);
+        assert_eq!(mine_field.live_frame(), format!("\x1b[H\x1b[2J{}", mine_field));
+    }
+
+    #[test]
+    fn neighbor_sets_of_a_number() {
+        let mut mine_field: MineField = "*..\n.*.\n...".parse().unwrap();
+        mine_field.set_flood(false);
+        mine_field.reveal(1, 0).unwrap();
+        mine_field.reveal(2, 0).unwrap();
+        mine_field.flag(0, 0).unwrap();
+        mine_field.flag(2, 1).unwrap();
+
+        let mut hidden = mine_field.unrevealed_neighbors(1, 0);
+        hidden.sort();
+        assert_eq!(hidden, [(0, 0), (0, 1), (1, 1), (2, 1)]);
+        let mut flagged = mine_field.flagged_neighbors(1, 0);
+        flagged.sort();
+        assert_eq!(flagged, [(0, 0), (2, 1)]);
+
+        // Corner cells have only 3 neighbors
+        assert_eq!(mine_field.unrevealed_neighbors(2, 2).len(), 3);
+        assert_eq!(mine_field.flagged_neighbors(2, 2), [(2, 1)]);
+    }
+
+    #[test]
+    fn mine_margins_count_each_row_and_column() {
+        let mut mine_field: MineField = "*..*\n.*..\n....".parse().unwrap();
+        assert_eq!(mine_field.mine_margins(), (vec![2, 1, 0], vec![1, 1, 0, 1]));
+
+        mine_field.set_color(false);
+        mine_field.set_margins(true);
+        let printed = mine_field.to_string();
+        let lines: Vec<&str> = printed.lines().collect();
+        assert_eq!(lines[3], " 1|            | 1   2");
+        assert_eq!(lines[5], " 3|            | 3   0");
+        assert_eq!(lines[8], "     1  1  0  1 ");
+    }
+
+    #[test]
+    fn a_safe_radius_keeps_the_square_around_the_first_cell_free() {
+        for radius in [1, 2] {
+            for seed in 0..20 {
+                let mut mine_field = MineField::new_seeded(9, 9, 30, seed).unwrap();
+                mine_field.set_safe_radius(radius);
+                mine_field.fill(4, 4);
+                let near_mine = mine_field
+                    .cells()
+                    .find(|&(x, y, cell)| cell.is_mine && x.abs_diff(4) <= radius && y.abs_diff(4) <= radius);
+                assert!(near_mine.is_none(), "radius {} seed {}", radius, seed);
+                assert_eq!(mine_field.cells().filter(|(_, _, cell)| cell.is_mine).count(), 30);
+            }
+        }
+    }
+
+    #[test]
+    fn available_chords_lists_only_satisfied_numbers() {
+        let mut mine_field: MineField = "*..\n...".parse().unwrap();
+        mine_field.set_flood(false);
+        mine_field.reveal(1, 0).unwrap();
+        mine_field.reveal(0, 1).unwrap();
+        assert!(mine_field.available_chords().is_empty());
+
+        mine_field.flag(0, 0).unwrap();
+        assert_eq!(mine_field.available_chords(), [(1, 0), (0, 1)]);
+
+        // Once the chord opened everything around it nothing is left to chord
+        mine_field.chord(1, 0).unwrap();
+        assert!(mine_field.available_chords().is_empty());
+    }
+
+    #[test]
+    fn column_labels_line_up_with_their_cells_on_a_wide_board() {
+        let row: String = (0..30).map(|x| if x % 7 == 3 { '*' } else { '.' }).collect();
+        let mut mine_field: MineField = format!("{}\n{}", row, ".".repeat(30)).parse().unwrap();
+        mine_field.set_color(false);
+        mine_field.set_peek(true);
+        let rendered = mine_field.to_string();
+        let lines: Vec<&str> = rendered.lines().collect();
+        let (header, cells, footer) = (lines[1].as_bytes(), lines[3].as_bytes(), lines[6].as_bytes());
+
+        // Labels are right-aligned in the cell they name, so their last digit sits above the cell's symbol
+        for x in 0..30 {
+            let column = 3 + x * 3 + 2;
+            let label = (x + 1).to_string();
+            assert_eq!(&header[column + 1 - label.len()..=column], label.as_bytes(), "column {}", x + 1);
+            assert_eq!(header[column + 1], b' ');
+            assert_eq!(footer[column], header[column]);
+            assert_eq!(cells[column] == b'*', x % 7 == 3, "column {}", x + 1);
+        }
+    }
+
+    #[test]
+    fn index_conversions_on_a_non_square_board() {
+        let mine_field = MineField::new(5, 3, 2).unwrap();
+        assert_eq!(mine_field.index_to_xy(0), (0, 0));
+        assert_eq!(mine_field.index_to_xy(4), (4, 0));
+        assert_eq!(mine_field.index_to_xy(5), (0, 1));
+        assert_eq!(mine_field.index_to_xy(14), (4, 2));
+        assert_eq!(mine_field.xy_to_index(3, 2), 13);
+        for index in 0..15 {
+            let (x, y) = mine_field.index_to_xy(index);
+            assert_eq!(mine_field.xy_to_index(x, y), index);
+        }
+
+        let mut by_index: MineField = "*....\n.....\n....*".parse().unwrap();
+        by_index.set_flood(false);
+        let mut by_xy: MineField = "*....\n.....\n....*".parse().unwrap();
+        by_xy.set_flood(false);
+        assert_eq!(by_index.reveal_index(7), by_xy.reveal(2, 1));
+        assert_eq!(by_index.field, by_xy.field);
+        assert_eq!(by_index.reveal_index(15), Err(BoardError::OutOfBounds(0, 3)));
+    }
+
+    #[test]
+    fn the_incremental_safe_count_matches_a_full_scan() {
+        let check = |mine_field: &MineField| {
+            assert_eq!((mine_field.revealed_safe, mine_field.total_safe), mine_field.safe_cell_counts());
+        };
+
+        let mut mine_field = MineField::new_seeded(9, 9, 10, 6).unwrap();
+        mine_field.set_mask(|x, y| (x, y) != (8, 8));
+        check(&mine_field);
+        mine_field.fill(4, 4);
+        check(&mine_field);
+
+        let mut rng = StdRng::seed_from_u64(6);
+        for step in 0.. {
+            if mine_field.reveal_random_safe(&mut rng).is_none() {
+                break;
+            }
+            check(&mine_field);
+            if step % 3 == 0 {
+                assert!(mine_field.undo());
+                check(&mine_field);
+            }
+
+            // The deduced flags let the numbers around them be chorded
+            mine_field.annotate();
+            if let Some(&(x, y)) = mine_field.available_chords().first() {
+                mine_field.chord(x, y).unwrap();
+                check(&mine_field);
+            }
+        }
+        assert_eq!(mine_field.state(), GameState::Won);
+
+        mine_field.reset();
+        check(&mine_field);
+    }
+
+    #[test]
+    fn the_first_opening_is_at_least_the_minimum_size() {
+        for seed in 0..20 {
+            let mut mine_field = MineField::new_seeded(16, 16, 40, seed).unwrap();
+            mine_field.set_min_first_opening(30);
+            mine_field.fill(0, 0);
+            assert!(mine_field.opening_size(0, 0) >= 30, "seed {}", seed);
+
+            let (_, revealed) = mine_field.reveal_cells(0, 0).unwrap();
+            assert!(revealed.len() >= 30);
+        }
+
+        // An opening that can never be reached keeps the last attempt instead of looping forever
+        let mut mine_field = MineField::new_seeded(9, 9, 70, 0).unwrap();
+        mine_field.set_min_first_opening(81);
+        mine_field.fill(4, 4);
+        assert_eq!(mine_field.cells().filter(|(_, _, cell)| cell.is_mine).count(), 70);
+    }
+
+    #[test]
+    fn boards_from_the_same_seed_compare_equal() {
+        let filled = |seed| {
+            let mut mine_field = MineField::new_seeded(9, 9, 10, seed).unwrap();
+            mine_field.fill(4, 4);
+            mine_field
+        };
+        assert!(filled(3) == filled(3));
+        assert!(filled(3) != filled(4));
+
+        // Progress is part of the game, display settings aren't
+        let mut played = filled(3);
+        played.set_unicode(true);
+        assert!(played == filled(3));
+        played.reveal(4, 4).unwrap();
+        assert!(played != filled(3));
+
+        let hash = |mine_field: &MineField| {
+            let mut hasher = std::hash::DefaultHasher::new();
+            mine_field.hash(&mut hasher);
+            hasher.finish()
+        };
+        assert_eq!(hash(&filled(3)), hash(&filled(3)));
+    }
+
+    #[test]
+    fn changing_a_fork_leaves_the_original_alone() {
+        let mut original = MineField::new_seeded(9, 9, 10, 1).unwrap();
+        original.fill(4, 4);
+        original.reveal(4, 4).unwrap();
+        let snapshot = original.to_string();
+
+        let mut fork = original.fork();
+        fork.flag(0, 0).unwrap();
+        fork.reveal_random_safe(&mut StdRng::seed_from_u64(1)).unwrap();
+        fork.set_unicode(true);
+        assert!(fork != original);
+        assert_eq!(original.to_string(), snapshot);
+        assert_eq!(original.moves(), 1);
+
+        // Undoing in the fork doesn't reach into the original's history
+        assert!(fork.undo());
+        assert!(fork.undo());
+        assert!(!fork.undo());
+        assert!(original.undo());
+    }
+
+    #[test]
+    fn auto_chord_opens_around_a_clicked_number_only_when_enabled() {
+        let mut mine_field: MineField = "*..\n...".parse().unwrap();
+        mine_field.set_flood(false);
+        mine_field.reveal(1, 0).unwrap();
+        mine_field.flag(0, 0).unwrap();
+
+        let mut manual = mine_field.fork();
+        assert_eq!(manual.reveal_cells(1, 0), Ok((GameState::Playing, Vec::new())));
+        assert_eq!(manual.moves(), 1);
+
+        mine_field.set_auto_chord_on_revealed(true);
+        let (state, revealed) = mine_field.reveal_cells(1, 0).unwrap();
+        assert_eq!((state, revealed.len()), (GameState::Won, 4));
+        assert_eq!(mine_field.moves(), 2);
+        assert_eq!(mine_field.actions().last(), Some(&Action::Chord { x: 1, y: 0 }));
+    }
+
+    #[test]
+    fn the_minimap_summarizes_each_block() {
+        let mut mine_field: MineField = "......\n......\n.....*\n.....*".parse().unwrap();
+        mine_field.set_flood(false);
+        mine_field.set_mask(|x, y| x >= 2 || y < 2);
+        mine_field.recompute_adjacency();
+        assert_eq!(mine_field.render_minimap(2), "###\n ##\n");
+
+        // A fully revealed block, one mostly revealed and one mostly hidden
+        mine_field.reveal_many(&[(0, 0), (1, 0), (0, 1), (1, 1), (2, 0), (3, 0), (2, 1), (4, 2)]).unwrap();
+        assert_eq!(mine_field.render_minimap(2), ".-#\n #+\n");
+        mine_field.reveal(3, 1).unwrap();
+        assert_eq!(mine_field.render_minimap(2), "..#\n #+\n");
+
+        // Blocks at the edge of the board are cut short
+        assert_eq!(mine_field.render_minimap(4), "-+\n");
+        assert_eq!(mine_field.render_minimap(0), mine_field.render_minimap(1));
+    }
+
+    #[test]
+    fn over_flagging_is_reported_once_flags_outnumber_mines() {
+        let mut mine_field: MineField = "*..\n...".parse().unwrap();
+        mine_field.flag(1, 0).unwrap();
+        assert!(!mine_field.is_over_flagged());
+        mine_field.flag(2, 0).unwrap();
+        assert!(mine_field.is_over_flagged());
+        assert_eq!(mine_field.remaining_mines(), -1);
+
+        // Turning a flag into a question mark takes it out of the count
+        mine_field.flag(2, 0).unwrap();
+        assert!(!mine_field.is_over_flagged());
+    }
+
+    #[test]
+    fn cell_at_shows_what_the_player_sees() {
+        let mut mine_field: MineField = "*..\n..#".parse().unwrap();
+        mine_field.set_flood(false);
+        mine_field.reveal(2, 0).unwrap();
+        mine_field.reveal(1, 0).unwrap();
+        mine_field.flag(0, 1).unwrap();
+        mine_field.flag(1, 1).unwrap();
+        mine_field.flag(1, 1).unwrap();
+        assert_eq!(mine_field.cell_at(0, 0), Some(CellView::Hidden));
+        assert_eq!(mine_field.cell_at(1, 0), Some(CellView::Revealed(1)));
+        assert_eq!(mine_field.cell_at(2, 0), Some(CellView::Revealed(0)));
+        assert_eq!(mine_field.cell_at(0, 1), Some(CellView::Flagged));
+        assert_eq!(mine_field.cell_at(1, 1), Some(CellView::Question));
+
+        mine_field.reveal(0, 0).unwrap();
+        assert_eq!(mine_field.cell_at(0, 0), Some(CellView::RevealedMine));
+
+        // Holes and cells off the board have nothing to show
+        assert_eq!(mine_field.cell_at(2, 1), None);
+        assert_eq!(mine_field.cell_at(3, 0), None);
+        assert_eq!(mine_field.cell_at(0, 2), None);
+    }
+
+    #[test]
+    fn filling_keeps_marks_placed_before() {
+        let mut mine_field = MineField::new_seeded(9, 9, 10, 1).unwrap();
+        mine_field.flag(8, 8).unwrap();
+        mine_field.flag(7, 8).unwrap();
+        mine_field.flag(7, 8).unwrap();
+        mine_field.fill(0, 0);
+        assert_eq!(mine_field.cell_at(8, 8), Some(CellView::Flagged));
+        assert_eq!(mine_field.cell_at(7, 8), Some(CellView::Question));
+        assert_eq!(mine_field.actions().len(), 3);
+
+        mine_field.fill_no_guess(0, 0);
+        assert_eq!(mine_field.cell_at(8, 8), Some(CellView::Flagged));
+        assert_eq!(mine_field.cell_at(7, 8), Some(CellView::Question));
+    }
+
+    #[test]
+    fn dangerous_numbers_have_more_mines_than_safe_cells_around_them() {
+        let mut mine_field: MineField = "*..\n*..\n...".parse().unwrap();
+        mine_field.set_flood(false);
+        mine_field.reveal(1, 1).unwrap();
+        mine_field.reveal(1, 0).unwrap();
+        mine_field.reveal(2, 0).unwrap();
+
+        // The 2 at (1, 1) has 2 mines among its 6 hidden neighbors, the 2 at (1, 0) has 2 among 3
+        assert!(!mine_field.is_dangerous(1, 1));
+        assert!(mine_field.is_dangerous(1, 0));
+        assert!(!mine_field.is_dangerous(2, 0));
+
+        // Flagging one of the mines leaves a single mine among 2 cells, which isn't more likely than not
+        mine_field.flag(0, 0).unwrap();
+        assert!(!mine_field.is_dangerous(1, 0));
+        assert!(!mine_field.is_dangerous(3, 0));
+        assert!(!mine_field.is_dangerous(0, 3));
+    }
+
+    #[test]
+    fn live_clear_below_skips_the_column_mine_counts() {
+        let mut mine_field = MineField::new(9, 5, 3).unwrap();
+        let lines = |mine_field: &MineField| mine_field.live_frame().lines().count();
+        assert_eq!(mine_field.live_clear_below(), format!("\x1b[{};1H\x1b[J", lines(&mine_field) + 1));
+
+        mine_field.set_margins(true);
+        assert_eq!(mine_field.live_clear_below(), format!("\x1b[{};1H\x1b[J", lines(&mine_field) + 1));
+    }
+
+    #[test]
+    fn running_out_of_time_loses_the_game() {
+        let mut mine_field = MineField::new_seeded(9, 9, 10, 3).unwrap();
+        mine_field.set_color(false);
+        mine_field.fill(2, 2);
+        mine_field.reveal(2, 2).unwrap();
+
+        assert_eq!(mine_field.time_out(), GameState::Lost);
+        assert!(mine_field.result_banner(GameState::Lost).contains("Time's up!"));
+        assert_eq!(mine_field.reveal(8, 8), Err(BoardError::GameOver));
+
+        mine_field.reset();
+        assert!(mine_field.result_banner(GameState::Lost).contains("You hit a mine!"));
+    }
+
+    #[test]
+    fn undo_takes_back_the_move() {
+        let mut mine_field = MineField::new_seeded(9, 9, 10, 3).unwrap();
+        mine_field.fill(2, 2);
+        mine_field.reveal(2, 2).unwrap();
+        mine_field.flag(0, 8).unwrap();
+        let before = mine_field.clone();
+        let (x, y, _) = mine_field.cells().find(|(_, _, cell)| !cell.is_mine && !cell.is_revealed).unwrap();
+        mine_field.reveal(x, y).unwrap();
+        assert_eq!(mine_field.moves(), 2);
+        assert!(mine_field != before);
+
+        assert!(mine_field.undo());
+        assert!(mine_field == before);
+        assert_eq!(mine_field.to_string(), before.to_string());
+        assert!(mine_field.cells().all(|(x, y, _)| mine_field.cell_at(x, y) == before.cell_at(x, y)));
+        assert_eq!(mine_field.moves(), 1);
+        assert_eq!(mine_field.revealed_count(), before.revealed_count());
+        assert_eq!(mine_field.actions(), &[Action::Reveal { x: 2, y: 2 }, Action::Flag { x: 0, y: 8 }]);
+    }
+
+    #[test]
+    fn load_rejects_a_header_that_doesnt_match_the_rows() {
+        let path = temp_path("corrupt.save");
+        for data in ["100000 100000 1 0\n*.\n..\n", "2 2 1 0\n*.\n", "2 2 3 0\n*.\n.*\n", "2 2 1 0\n*..\n..\n"] {
+            fs::write(&path, data).unwrap();
+            assert_eq!(MineField::load(&path).unwrap_err().kind(), io::ErrorKind::InvalidData, "{:?}", data);
+        }
+
+        // Nothing placed yet is fine, the mines come with the first reveal
+        fs::write(&path, "2 2 1 0\n..\n..\n").unwrap();
+        assert!(!MineField::load(&path).unwrap().is_filled());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn only_a_flag_on_a_safe_cell_is_wrong() {
+        let mut mine_field: MineField = "*..\n...".parse().unwrap();
+        mine_field.flag(0, 0).unwrap();
+        mine_field.flag(1, 0).unwrap();
+        assert!(!mine_field.is_wrong_flag(0, 0));
+        assert!(mine_field.is_wrong_flag(1, 0));
+        assert!(!mine_field.is_wrong_flag(2, 0));
+        assert!(!mine_field.is_wrong_flag(3, 0));
+
+        // A question mark is no flag
+        mine_field.flag(1, 0).unwrap();
+        assert!(!mine_field.is_wrong_flag(1, 0));
+    }
+}
@@ -1,251 +1,958 @@
-use rand::Rng;
-use std::io::{self, Write};
+use minesweeper::{Adjacency, Cell, CellView, ColumnLabels, Difficulty, Distribution, GameState, MineField, load_scores, save_scores};
+use rustyline::DefaultEditor;
+use std::cell::RefCell;
+use std::fs;
+use std::io::{self, IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process;
 use std::str::FromStr;
+use std::time::{Duration, Instant};
 
-// Game cell state constants
-const REVEALED_MINE: i8 = 9;        // A revealed mine (game over)
-const UNREVEALED_MINE: i8 = -9;     // A hidden mine
-const REVEALED_EMPTY: i8 = 127;     // A revealed empty cell with no adjacent mines
-const UNREVEALED_EMPTY: i8 = 0;     // A hidden empty cell
 const MAX_SIZE: usize = 99;         // Maximum allowed size for the game board (so that the board formatting doesn't break)
+const MINIMAP_SIZE: usize = 33;     // Largest number of characters across the mini-map
+const SAVE_PATH: &str = "minesweeper.save";  // File used by the save and load commands when none is given
+const SCORES_FILE: &str = ".minesweeper_scores";  // File in the home directory holding the best times
+
+thread_local! {
+    // Lines of the replay file, read instead of stdin when one is given
+    static REPLAY_INPUT: RefCell<Option<std::vec::IntoIter<String>>> = const { RefCell::new(None) };
+}
 
 fn main() {
-    // Get game parameters from user
-    let (width, height) = get_input_vec2("Mine field size (width height): ", MAX_SIZE, MAX_SIZE);
-    let mine_count: usize = get_input("Mine count: ");
+    let args = parse_args(std::env::args().skip(1)).unwrap_or_else(|error| exit_with_usage(&error));
 
-    // Validate and adjust parameters if needed
-    let (width, height) = get_valid_size(width, height);
-    let mine_count = get_valid_mine_count(width, height, mine_count);
+    // Answer every prompt from the replay file instead of asking the user
+    if let Some(path) = &args.replay_file {
+        let replay = fs::read_to_string(path).unwrap_or_else(|err| {
+            eprintln!("Could not read the replay file: {}", err);
+            process::exit(1);
+        });
+        let lines: Vec<String> = replay.lines().map(str::to_string).collect();
+        REPLAY_INPUT.set(Some(lines.into_iter()));
+    }
+
+    // Get game parameters from the command line, or from the user either as a preset or entered manually
+    // Closing the input at any prompt quits the game
+    let Some((width, height, mine_count)) = args.settings.or_else(get_settings) else {
+        return;
+    };
 
     println!(
         "Generating {}x{} mine field with {} mines:",
         width, height, mine_count
     );
 
-    // Initialize and set up the game
-    let mut mine_field = MineField::new(width, height, mine_count);
-    mine_field.fill();
-    mine_field.print();
+    // Initialize the game, mines are placed once the first cell is chosen
+    let mut mine_field = match args.seed {
+        Some(seed) => MineField::new_seeded(width, height, mine_count, seed),
+        None => MineField::new(width, height, mine_count),
+    }
+    .expect("mine field size is validated");
+    mine_field.set_wrap(args.wrap);
+    if args.orthogonal {
+        mine_field.set_adjacency(Adjacency::VonNeumann);
+    }
+    apply_options(&mut mine_field, &args);
 
-    // Main game loop
-    loop {
-        let (x, y) = get_input_vec2("Reveal coordinates (x y): ", width, height);
-        
-        // Convert from 1-based user coordinates to 0-based internal coordinates
-        if mine_field.reveal(x - 1, y - 1) {
-            println!("Game over! You hit a mine!");
-            break;
-        }
-        mine_field.print();
+    // Play the scripted reveals instead of asking the user
+    if let Some(path) = &args.script {
+        run_script(&mut mine_field, Path::new(path), args.no_guess);
+        return;
     }
 
-    // TODO: Allow exiting the game early and replaying, finishing the game, add colors
-}
+    // Redrawing cells in place only works when the board is shown on a terminal,
+    // moving a cursor with the arrow keys also needs the keys to come from one
+    let live = (args.live || args.cursor) && io::stdout().is_terminal();
+    let cursor_mode = args.cursor && live && io::stdin().is_terminal() && cfg!(unix);
+    let mut cursor = (0, 0);
 
-/// Represents the minesweeper game board
-struct MineField {
-    field: Box<[Box<[i8]>]>,  // 2D array of cell values
-    mine_count: usize,        // Total number of mines on the board
-}
+    loop {
+        draw_board(&mine_field, live);
 
-impl MineField {
-    /// Creates a new empty mine field with the specified dimensions
-    fn new(width: usize, height: usize, mine_count: usize) -> Self {
-        let field = (0..height)
-            .map(|_| vec![0i8; width].into_boxed_slice())
-            .collect::<Vec<_>>()
-            .into_boxed_slice();
+        // In time attack mode the clock starts as soon as the board is shown
+        let mut deadline = args.time_limit.map(|limit| Instant::now() + limit);
 
-        Self { field, mine_count }
-    }
+        // Main game loop
+        loop {
+            let command = if cursor_mode && fits_terminal(&mine_field) {
+                get_cursor_command(&mut mine_field, &mut cursor)
+            } else {
+                get_command(
+                    "Reveal coordinates (x y), flag (f x y), chord (c x y), undo (u), hint (h), mini-map (map), mark deductions (m), auto solve (auto), explain (explain x y), demo (demo), save (s [file]), load (l [file]), restart or give up (q): ",
+                    mine_field.width(),
+                    mine_field.height(),
+                )
+            };
+            let Some(command) = command else {
+                // A replay file ending mid-game still shows where it left off
+                if args.replay_file.is_some() {
+                    print_board(&mine_field);
+                }
+                return;
+            };
 
-    /// Fills the mine field with mines and calculates adjacent mine counts
-    fn fill(&mut self) {
-        self.zero();
-        let mut rng = rand::rng();
-        let mut placed_mines = 0;
+            // The input can't be interrupted, so running out of time is noticed once the next command arrives
+            if deadline.is_some_and(is_expired) {
+                println!("Time's up, that move came too late and wasn't made");
+                let state = mine_field.time_out();
+                show_loss(&mut mine_field, live, state);
+                break;
+            }
 
-        let width = self.field[0].len();
-        let height = self.field.len();
+            // Rows counted from the bottom are turned back into rows counted from the top
+            let command = match command {
+                Command::Reveal(x, y) if args.bottom_origin => Command::Reveal(x, mine_field.height() + 1 - y),
+                Command::Flag(x, y) if args.bottom_origin => Command::Flag(x, mine_field.height() + 1 - y),
+                Command::Chord(x, y) if args.bottom_origin => Command::Chord(x, mine_field.height() + 1 - y),
+                Command::Explain(x, y) if args.bottom_origin => Command::Explain(x, mine_field.height() + 1 - y),
+                command => command,
+            };
 
-        // Place mines randomly
-        while placed_mines < self.mine_count {
-            let x = rng.random_range(0..width);
-            let y = rng.random_range(0..height);
+            // Clear the previous prompt and messages so the screen doesn't scroll away from the board
+            if live {
+                print!("{}", mine_field.live_clear_below());
+            }
+            let before = snapshot(&mine_field);
 
-            // Skip if there's already a mine here
-            if self.field[y][x] == UNREVEALED_MINE {
+            // Holes cut out of a shaped board can't be played
+            if let Command::Reveal(x, y) | Command::Flag(x, y) | Command::Chord(x, y) | Command::Explain(x, y) = command
+                && !mine_field.is_active(x - 1, y - 1)
+            {
+                println!("That cell is not part of the mine field");
                 continue;
             }
 
-            // Place a mine
-            self.field[y][x] = UNREVEALED_MINE;
-
-            // Calculate bounds for adjacent cells
-            let start_y = y.saturating_sub(1);
-            let end_y = (y + 1).min(height - 1);
-            let start_x = x.saturating_sub(1);
-            let end_x = (x + 1).min(width - 1);
+            // The tutorial holds off further reveals until the player has flagged a mine correctly
+            if args.tutorial
+                && let Err(reason) = tutorial_rule(&mine_field, &command)
+            {
+                println!("{}", reason);
+                continue;
+            }
 
-            // Update adjacent cell counts
-            for y in start_y..=end_y {
-                for x in start_x..=end_x {
-                    if self.field[y][x] != UNREVEALED_MINE {
-                        self.field[y][x] -= 1;
+            let state = match command {
+                Command::Reveal(x, y) => {
+                    fill_before_reveal(&mut mine_field, x - 1, y - 1, args.no_guess);
+                    let (state, revealed) = mine_field.reveal_cells(x - 1, y - 1).expect("coordinates are validated");
+                    if args.warn_danger && state == GameState::Playing {
+                        warn_dangerous(&mine_field, &revealed);
+                    }
+                    state
+                }
+                Command::Flag(x, y) => {
+                    mine_field.flag(x - 1, y - 1).expect("coordinates are validated");
+                    if args.tutorial && mine_field.is_wrong_flag(x - 1, y - 1) {
+                        println!("There is no mine under that flag");
+                    }
+                    if mine_field.is_over_flagged() {
+                        println!("There are more flags than mines, at least one of them must be wrong");
+                    }
+                    mine_field.state()
+                }
+                Command::Chord(x, y) => mine_field.chord(x - 1, y - 1).expect("coordinates are validated"),
+                Command::Save(path) => {
+                    match mine_field.save(Path::new(&path)) {
+                        Ok(()) => println!("Game saved to {}", path),
+                        Err(err) => println!("Could not save the game: {}", err),
+                    }
+                    mine_field.state()
+                }
+                Command::Load(path) => {
+                    match MineField::load(Path::new(&path)) {
+                        Ok(loaded) => {
+                            mine_field = loaded;
+                            apply_options(&mut mine_field, &args);
+                            println!("Game loaded from {}", path);
+                        }
+                        Err(err) => println!("Could not load the game: {}", err),
+                    }
+                    mine_field.state()
+                }
+                Command::Hint => {
+                    print_hint(&mine_field);
+                    mine_field.state()
+                }
+                Command::Map => {
+                    // Scale the overview down so even the biggest boards fit on the screen
+                    let scale = mine_field.width().max(mine_field.height()).div_ceil(MINIMAP_SIZE);
+                    print!("{}", mine_field.render_minimap(scale));
+                    println!("Each character covers {0}x{0} cells: # hidden, + mostly hidden, - mostly revealed, . revealed", scale);
+                    mine_field.state()
+                }
+                Command::Annotate => {
+                    match mine_field.annotate() {
+                        0 => println!("Nothing new can be deduced"),
+                        marked => println!("Marked {} cells, flags are mines and question marks are safe", marked),
                     }
+                    mine_field.state()
+                }
+                Command::Undo => {
+                    if !mine_field.undo() {
+                        println!("There is no move to undo");
+                    }
+                    mine_field.state()
+                }
+                Command::Quit => {
+                    mine_field.reveal_all_mines();
+                    print_board(&mine_field);
+                    println!("You gave up!");
+                    break;
+                }
+                Command::Auto => {
+                    // Misplaced flags can make the solver reveal a mine
+                    mine_field.solve();
+                    mine_field.state()
+                }
+                Command::Explain(x, y) => {
+                    match mine_field.explain(x - 1, y - 1) {
+                        Some(explanation) => println!("{}", explanation),
+                        None => println!("Nothing can be deduced about that cell from its neighbors"),
+                    }
+                    mine_field.state()
+                }
+                Command::Demo => {
+                    match mine_field.reveal_random_safe(&mut rand::rng()) {
+                        Some((x, y)) => println!("Revealed ({})", mine_field.cell_label(x, y)),
+                        None => println!("There are no safe cells left to reveal"),
+                    }
+                    mine_field.state()
+                }
+                Command::Restart => {
+                    mine_field.reset();
+                    deadline = args.time_limit.map(|limit| Instant::now() + limit);
+                    println!("Starting over");
+                    mine_field.state()
+                }
+            };
+
+            match state {
+                GameState::Playing => {}
+                GameState::Won => {
+                    redraw_board(&mine_field, live, &before);
+                    mine_field.print_result(state);
+                    record_best_time(&mine_field);
+                    break;
+                }
+                GameState::Lost => {
+                    show_loss(&mut mine_field, live, state);
+                    break;
                 }
             }
+            redraw_board(&mine_field, live, &before);
+        }
 
-            placed_mines += 1;
+        // Start over on the same mine field size, or exit
+        if get_yes_no("Play again? (y/n): ") != Some(true) {
+            break;
         }
+        mine_field.reset();
     }
+}
 
-    /// Reveals a cell at the given coordinates
-    /// Returns true if a mine was revealed (game over), false otherwise
-    fn reveal(&mut self, x: usize, y: usize) -> bool {
-        match self.field[y][x] {
-            // Empty cell - reveal it and all adjacent empty cells
-            UNREVEALED_EMPTY => {
-                self.field[y][x] = REVEALED_EMPTY;
-                self.reveal_adjacent(x, y);
-                false
-            }
-            // Mine - game over
-            UNREVEALED_MINE => {
-                self.field[y][x] = REVEALED_MINE;
-                true
-            }
-            // Number cell - just reveal it
-            _ => {
-                self.field[y][x] = self.field[y][x].abs();
-                false
+/// Shows where all the mines were and the result of a lost game
+fn show_loss(mine_field: &mut MineField, live: bool, state: GameState) {
+    mine_field.reveal_all_mines();
+    draw_board(mine_field, live);
+    mine_field.print_result(state);
+}
+
+/// Draws the whole mine field, at the top of a cleared screen in live mode
+fn draw_board(mine_field: &MineField, live: bool) {
+    if live && fits_terminal(mine_field) {
+        print!("{}", mine_field.live_frame());
+    } else {
+        print_board(mine_field);
+    }
+}
+
+/// Draws the mine field after a move, in live mode by redrawing only the cells that changed since `before`
+fn redraw_board(mine_field: &MineField, live: bool, before: &(usize, Vec<Cell>)) {
+    let (width, cells) = before;
+    if !live || !fits_terminal(mine_field) || *width != mine_field.width() || cells.len() != mine_field.cells().count() {
+        draw_board(mine_field, live);
+        return;
+    }
+
+    let changed: Vec<(usize, usize)> = mine_field
+        .cells()
+        .zip(cells)
+        .filter(|((_, _, cell), old)| cell != old)
+        .map(|((x, y, _), _)| (x, y))
+        .collect();
+    print!("{}", mine_field.live_update(&changed));
+    // The update doesn't end with a newline, so it has to be flushed before the prompt is drawn
+    io::stdout().flush().ok();
+}
+
+/// Returns the width and cells of the mine field, to find the cells a move changed
+fn snapshot(mine_field: &MineField) -> (usize, Vec<Cell>) {
+    (mine_field.width(), mine_field.cells().map(|(_, _, cell)| *cell).collect())
+}
+
+/// Returns true if the mine field can be printed without splitting it into panels
+fn fits_terminal(mine_field: &MineField) -> bool {
+    terminal_width().is_none_or(|width| mine_field.printed_width() <= width)
+}
+
+/// Prints the mine field, split into panels of columns if it is wider than the terminal
+fn print_board(mine_field: &MineField) {
+    match terminal_width() {
+        Some(width) if mine_field.printed_width() > width => mine_field
+            .render_paged(&mut io::stdout(), width)
+            .expect("failed printing to stdout"),
+        _ => mine_field.print(),
+    }
+}
+
+/// Number of columns of the terminal, or `None` if stdout is not a terminal or its size is unknown
+/// The `COLUMNS` environment variable takes precedence over asking the terminal
+fn terminal_width() -> Option<usize> {
+    if let Some(columns) = std::env::var("COLUMNS").ok().and_then(|columns| columns.parse().ok()) {
+        return Some(columns);
+    }
+    if !io::stdout().is_terminal() {
+        return None;
+    }
+
+    #[cfg(unix)]
+    {
+        let mut size = libc::winsize { ws_row: 0, ws_col: 0, ws_xpixel: 0, ws_ypixel: 0 };
+        // SAFETY: TIOCGWINSZ only writes a `winsize` into the given pointer, which is valid for the call
+        let result = unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) };
+        (result == 0 && size.ws_col > 0).then_some(size.ws_col as usize)
+    }
+    #[cfg(not(unix))]
+    None
+}
+
+/// Reveals the cells listed in a script file, one "x y" pair per line, and prints the result
+/// Exits with an error code if the script can't be read or contains invalid coordinates
+fn run_script(mine_field: &mut MineField, path: &Path, no_guess: bool) {
+    let script = fs::read_to_string(path).unwrap_or_else(|err| {
+        eprintln!("Could not read the script: {}", err);
+        process::exit(1);
+    });
+
+    // Convert from 1-based script coordinates to 0-based internal coordinates
+    let mut coords = Vec::new();
+    for (number, line) in script.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()) {
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        match parse_vec2(&parts, mine_field.width(), mine_field.height()) {
+            Ok((x, y)) => coords.push((x - 1, y - 1)),
+            Err(error) => {
+                eprintln!("Invalid coordinates on line {} of the script: {}", number + 1, error);
+                process::exit(1);
             }
         }
     }
 
-    /// Recursively reveals adjacent cells when an empty cell is revealed
-    fn reveal_adjacent(&mut self, x: usize, y: usize) {
-        let width = self.field[0].len();
-        let height = self.field.len();
-
-        // Calculate bounds for adjacent cells
-        let start_y = y.saturating_sub(1);
-        let end_y = (y + 1).min(height - 1);
-        let start_x = x.saturating_sub(1);
-        let end_x = (x + 1).min(width - 1);
-        
-        // Check all adjacent cells
-        for dy in start_y..=end_y {
-            for dx in start_x..=end_x {
-                // Skip the current cell
-                if dx == x && dy == y {
-                    continue;
-                }
-                
-                // Reveal adjacent cells if they're within bounds
-                if dx < width && dy < height {
-                    self.reveal(dx, dy);
-                }
-            }
+    // Generate the mine field around the first revealed cell so it is always safe
+    if let Some(&(x, y)) = coords.first() {
+        fill(mine_field, x, y, no_guess);
+    }
+
+    let state = mine_field.reveal_many(&coords).unwrap_or_else(|err| {
+        eprintln!("The script can't be played: {}", err);
+        process::exit(1);
+    });
+    if state == GameState::Lost {
+        mine_field.reveal_all_mines();
+    }
+    print_board(mine_field);
+    match state {
+        GameState::Playing => println!("The script ended with the game still in progress"),
+        GameState::Won => println!("The script cleared the mine field!"),
+        GameState::Lost => println!("The script hit a mine!"),
+    }
+}
+
+/// Places the mines so the cell at the given coordinates is safe, warning if a board that can be
+/// solved without guessing was asked for but couldn't be generated
+fn fill(mine_field: &mut MineField, x: usize, y: usize, no_guess: bool) {
+    if !no_guess {
+        mine_field.fill(x, y);
+    } else if !mine_field.fill_no_guess(x, y) {
+        eprintln!("Warning: no board solvable without guessing was found, this one may need a guess");
+    }
+}
+
+/// Generates the mine field around the first cell a reveal opens, so that cell is always safe
+/// A reveal bouncing off a flag opens nothing, so it leaves the mine field unfilled
+fn fill_before_reveal(mine_field: &mut MineField, x: usize, y: usize, no_guess: bool) {
+    if !mine_field.is_filled() && mine_field.cell_at(x, y) != Some(CellView::Flagged) {
+        fill(mine_field, x, y, no_guess);
+    }
+}
+
+/// Compares the time of a won game against the best time for the same board size and mine count,
+/// storing it if it was beaten
+fn record_best_time(mine_field: &MineField) {
+    let path = scores_path();
+    let mut scores = load_scores(&path);
+    let key = (mine_field.width(), mine_field.height(), mine_field.mine_count());
+    let time = mine_field.elapsed();
+
+    if scores.get(&key).is_none_or(|&best| time < best) {
+        scores.insert(key, time);
+        println!("New best time!");
+        if let Err(err) = save_scores(&path, &scores) {
+            println!("Could not save the best time: {}", err);
         }
     }
+}
+
+/// Returns the path of the best times file, in the home directory if there is one
+fn scores_path() -> PathBuf {
+    std::env::var_os("HOME")
+        .map(PathBuf::from)
+        .unwrap_or_default()
+        .join(SCORES_FILE)
+}
+
+/// Prints the suggested safest cells with the labels the board shows them at
+fn print_hint(mine_field: &MineField) {
+    const MAX_SHOWN: usize = 10;  // Don't flood the screen on large, mostly unknown boards
+
+    let cells = mine_field.safest_cells();
+    if cells.is_empty() {
+        println!("There are no cells left to reveal");
+        return;
+    }
+
+    print!("Safest cells:");
+    for &(x, y) in cells.iter().take(MAX_SHOWN) {
+        print!(" ({})", mine_field.cell_label(x, y));
+    }
+    if cells.len() > MAX_SHOWN {
+        print!(" and {} more", cells.len() - MAX_SHOWN);
+    }
+    println!();
+}
+
+/// Checks whether the tutorial lets the player make the move yet
+/// After the first reveal, cells can only be revealed or chorded once a flag is placed and every flag is
+/// on a mine, returns what the player has to do otherwise
+fn tutorial_rule(mine_field: &MineField, command: &Command) -> Result<(), String> {
+    if !matches!(command, Command::Reveal(..) | Command::Chord(..)) || mine_field.moves() == 0 {
+        return Ok(());
+    }
+
+    let mut flags = mine_field.cells().filter(|(_, _, cell)| cell.is_flagged()).peekable();
+    if flags.peek().is_none() {
+        return Err("Flag a mine you are sure of before revealing more cells".to_string());
+    }
+    match flags.find(|(_, _, cell)| !cell.is_mine) {
+        Some((x, y, _)) => Err(format!(
+            "The flag at ({}) is not on a mine, remove it before revealing more cells",
+            mine_field.cell_label(x, y)
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Gently points out the newly revealed numbers that are more likely than not to have a mine
+/// behind any hidden neighbor
+fn warn_dangerous(mine_field: &MineField, revealed: &[(usize, usize)]) {
+    let dangerous: Vec<String> = revealed
+        .iter()
+        .filter(|&&(x, y)| mine_field.is_dangerous(x, y))
+        .map(|&(x, y)| format!("({})", mine_field.cell_label(x, y)))
+        .collect();
+    if !dangerous.is_empty() {
+        println!("Careful, most hidden cells around {} are mines", dangerous.join(", "));
+    }
+}
 
-    /// Resets all cells to empty
-    fn zero(&mut self) {
-        for row in self.field.iter_mut() {
-            row.fill(0);
+/// Returns true once the deadline has passed
+fn is_expired(deadline: Instant) -> bool {
+    Instant::now() >= deadline
+}
+
+/// Applies the options chosen on the command line that a saved game doesn't keep,
+/// so a loaded game is generated, played and shown the same way as a new one
+fn apply_options(mine_field: &mut MineField, args: &Args) {
+    if args.no_color {
+        mine_field.set_color(false);
+    }
+    mine_field.set_unicode(args.unicode);
+    if args.clustered {
+        mine_field.set_distribution(Distribution::Clustered);
+    }
+    if let Some(radius) = args.safe_radius {
+        mine_field.set_safe_radius(radius);
+    }
+    mine_field.set_min_first_opening(args.min_opening);
+    mine_field.set_flood(!args.no_flood);
+    mine_field.set_peek(args.peek);
+    mine_field.set_flag_overlay(args.flag_overlay);
+    mine_field.set_auto_flag(args.auto_flag);
+    mine_field.set_auto_chord_on_revealed(args.auto_chord);
+    mine_field.set_margins(args.margins);
+    mine_field.set_column_labels(if args.letters { ColumnLabels::Alphabetic } else { ColumnLabels::Numeric });
+    mine_field.set_bottom_origin(args.bottom_origin);
+}
+
+/// Options passed on the command line
+#[derive(Default)]
+struct Args {
+    seed: Option<u64>,  // Seed for reproducible mine placement
+    no_color: bool,     // Whether to force plain output without colors
+    no_guess: bool,     // Whether to only generate boards that can be solved without guessing
+    unicode: bool,      // Whether to draw the board with Unicode box-drawing characters and glyphs
+    wrap: bool,         // Whether the board edges wrap around
+    orthogonal: bool,   // Whether only the 4 orthogonal cells count as neighbors
+    no_flood: bool,     // Whether revealing an empty cell opens only that cell
+    peek: bool,         // Whether hidden mines are shown on the board, for debugging
+    flag_overlay: bool, // Whether revealed numbers are shown with the flags around them, for debugging
+    letters: bool,      // Whether columns are labeled with letters instead of numbers
+    bottom_origin: bool,  // Whether rows are numbered from the bottom instead of the top
+    auto_flag: bool,    // Whether the leftover mines are flagged once the game is won
+    auto_chord: bool,   // Whether revealing a revealed number chords it
+    clustered: bool,    // Whether mines gather in clusters instead of being spread evenly
+    live: bool,         // Whether moves redraw only the changed cells instead of the whole board
+    cursor: bool,       // Whether cells are picked with a cursor moved by the arrow keys
+    margins: bool,      // Whether the number of mines in each row and column is shown
+    safe_radius: Option<usize>,  // Rings of cells around the first reveal kept free of mines
+    min_opening: usize, // Cells the first reveal has to open at least
+    tutorial: bool,     // Whether reveals have to wait until a mine is flagged correctly
+    warn_danger: bool,  // Whether newly revealed numbers next to likely mines are pointed out
+    time_limit: Option<Duration>,  // Time the player has to win each game before it counts as lost
+    density: Option<f64>,  // Fraction of the cells that are mines, used instead of a mine count
+    settings: Option<(usize, usize, usize)>,  // Width, height and mine count given instead of prompting
+    script: Option<String>,  // File with reveal coordinates to play instead of prompting
+    replay_file: Option<String>,  // File with a command per line to read instead of stdin
+}
+
+/// Parses the command line arguments (without the program name)
+/// Returns a description of the problem if they are invalid
+fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Args, String> {
+    let mut parsed = Args::default();
+    let mut numbers = Vec::new();
+    let mut iter = args.into_iter();
+
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--seed" => match iter.next().and_then(|seed| seed.parse().ok()) {
+                Some(seed) => parsed.seed = Some(seed),
+                None => return Err("--seed needs a number".to_string()),
+            },
+            "--script" => match iter.next() {
+                Some(path) => parsed.script = Some(path),
+                None => return Err("--script needs a file".to_string()),
+            },
+            "--replay-file" => match iter.next() {
+                Some(path) => parsed.replay_file = Some(path),
+                None => return Err("--replay-file needs a file".to_string()),
+            },
+            "--density" => match iter.next().and_then(|density| density.parse().ok()) {
+                Some(density) => parsed.density = Some(density),
+                None => return Err("--density needs a number".to_string()),
+            },
+            "--time-limit" => match iter.next().and_then(|seconds| seconds.parse().ok()) {
+                Some(seconds) => parsed.time_limit = Some(Duration::from_secs(seconds)),
+                None => return Err("--time-limit needs a number of seconds".to_string()),
+            },
+            "--safe-radius" => match iter.next().and_then(|radius| radius.parse().ok()) {
+                Some(radius) => parsed.safe_radius = Some(radius),
+                None => return Err("--safe-radius needs a number".to_string()),
+            },
+            "--min-opening" => match iter.next().and_then(|cells| cells.parse().ok()) {
+                Some(cells) => parsed.min_opening = cells,
+                None => return Err("--min-opening needs a number of cells".to_string()),
+            },
+            "--no-color" => parsed.no_color = true,
+            "--no-guess" => parsed.no_guess = true,
+            "--unicode" => parsed.unicode = true,
+            "--wrap" => parsed.wrap = true,
+            "--orthogonal" => parsed.orthogonal = true,
+            "--no-flood" => parsed.no_flood = true,
+            "--peek" => parsed.peek = true,
+            "--flag-overlay" => parsed.flag_overlay = true,
+            "--letters" => parsed.letters = true,
+            "--bottom-origin" => parsed.bottom_origin = true,
+            "--auto-flag" => parsed.auto_flag = true,
+            "--auto-chord" => parsed.auto_chord = true,
+            "--clustered" => parsed.clustered = true,
+            "--live" => parsed.live = true,
+            "--cursor" => parsed.cursor = true,
+            "--margins" => parsed.margins = true,
+            "--tutorial" => parsed.tutorial = true,
+            "--warn-danger" => parsed.warn_danger = true,
+            _ => match arg.parse::<usize>() {
+                Ok(number) => numbers.push(number),
+                Err(_) => return Err(format!("Unknown argument: {}", arg)),
+            },
         }
     }
 
-    /// Displays the current state of the mine field
-    fn print(&self) {
-        let width = self.field[0].len();
-        
-        // Print top coordinates
-        print!("    ");
-        for x in 1..=width {
-            print!("{:2} ", x);
+    // Either all three of width, height and mine count are given, or none of them
+    // With a density only the width and height are given
+    match (&numbers[..], parsed.density) {
+        ([], None) => {}
+        (&[width, height, mine_count], None) => {
+            let (width, height) = get_checked_size(width, height)?;
+            let mine_count = get_valid_mine_count(width, height, mine_count)?;
+            parsed.settings = Some((width, height, mine_count));
         }
-        println!();
-        
-        // Print top border
-        print!("  +");
-        for _ in 0..width {
-            print!("---");
+        (&[width, height], Some(density)) => {
+            let (width, height) = get_checked_size(width, height)?;
+            let mine_count = MineField::density_mine_count(width, height, density).map_err(|err| err.to_string())?;
+            parsed.settings = Some((width, height, mine_count));
         }
-        println!("+");
-
-        // Print each row with its y-coordinate
-        for (y, row) in self.field.iter().enumerate() {
-            print!("{:2}|", y + 1);
-            for &cell in row.iter() {
-                match cell {
-                    REVEALED_EMPTY => print!("  ."),
-                    n if n > 0 => print!(" {:2}", n),
-                    _ => print!("   "),
-                }
+        (_, None) => return Err("Expected a width, height and mine count".to_string()),
+        (_, Some(_)) => return Err("--density expects a width and height".to_string()),
+    }
+    Ok(parsed)
+}
+
+/// Prints the error and the command line usage and exits with an error code
+fn exit_with_usage(error: &str) -> ! {
+    eprintln!("{}", error);
+    eprintln!("Usage: minesweeper [--seed <number>] [--time-limit <seconds>] [--safe-radius <rings>] [--min-opening <cells>] [--no-color] [--no-guess] [--unicode] [--wrap] [--orthogonal] [--no-flood] [--peek] [--flag-overlay] [--letters] [--bottom-origin] [--auto-flag] [--auto-chord] [--clustered] [--live] [--cursor] [--margins] [--tutorial] [--warn-danger] [--script <file>] [--replay-file <file>] [<width> <height> <mines> | --density <fraction> <width> <height>]");
+    process::exit(1);
+}
+
+/// A single move entered by the player
+#[derive(Debug, PartialEq, Eq)]
+enum Command {
+    Reveal(usize, usize), // Reveal the cell at the given coordinates
+    Flag(usize, usize),   // Toggle a flag on the cell at the given coordinates
+    Chord(usize, usize),  // Reveal the neighbors of a number whose mines are all flagged
+    Save(String),         // Save the game to the given file
+    Load(String),         // Load a game from the given file
+    Hint,                 // Show the cells least likely to hold a mine
+    Map,                  // Show a scaled down overview of the board
+    Annotate,             // Flag the provable mines and question-mark the provably safe cells
+    Auto,                 // Let the solver open and flag every cell it can deduce
+    Demo,                 // Cheat by revealing a random cell without a mine
+    Explain(usize, usize),  // Describe why the cell at the given coordinates is safe or a mine
+    Undo,                 // Hide the cells revealed by the last move again
+    Restart,              // Start a new game on a mine field of the same size
+    Quit,                 // Give up and show where all the mines were
+}
+
+/// A key press in cursor mode
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Key {
+    Up,       // Move the cursor one row up
+    Down,     // Move the cursor one row down
+    Left,     // Move the cursor one column left
+    Right,    // Move the cursor one column right
+    Reveal,   // Reveal the cell under the cursor
+    Flag,     // Toggle a flag on the cell under the cursor
+    Chord,    // Chord the number under the cursor
+    Quit,     // Give up
+    Exit,     // Leave the game, like closing the input
+}
+
+/// Turns the bytes a single key press sends into a key, arrow keys arriving as escape sequences
+fn parse_key(bytes: &[u8]) -> Option<Key> {
+    match bytes {
+        b"\x1b[A" | b"k" => Some(Key::Up),
+        b"\x1b[B" | b"j" => Some(Key::Down),
+        b"\x1b[D" | b"h" => Some(Key::Left),
+        b"\x1b[C" | b"l" => Some(Key::Right),
+        b" " | b"\r" => Some(Key::Reveal),
+        b"f" => Some(Key::Flag),
+        b"c" => Some(Key::Chord),
+        b"q" => Some(Key::Quit),
+        [3] | [4] => Some(Key::Exit),  // Ctrl-C and Ctrl-D
+        _ => None,
+    }
+}
+
+/// Moves the cursor one cell in the direction of an arrow key, stopping at the edges of the board
+/// Other keys leave it where it is
+fn move_cursor((x, y): (usize, usize), key: Key, width: usize, height: usize) -> (usize, usize) {
+    match key {
+        Key::Up => (x, y.saturating_sub(1)),
+        Key::Down => (x, (y + 1).min(height - 1)),
+        Key::Left => (x.saturating_sub(1), y),
+        Key::Right => ((x + 1).min(width - 1), y),
+        _ => (x, y),
+    }
+}
+
+/// Reads key presses, moving the highlighted cursor, until one makes a move on the cell under it
+/// The board has to be on screen already, drawn by `draw_board` in live mode
+/// Returns None if the input was closed or the terminal couldn't be switched to raw input
+fn get_cursor_command(mine_field: &mut MineField, cursor: &mut (usize, usize)) -> Option<Command> {
+    // A loaded board can be smaller than the one the cursor was on
+    let (width, height) = (mine_field.width(), mine_field.height());
+    *cursor = (cursor.0.min(width - 1), cursor.1.min(height - 1));
+    mine_field.set_cursor(Some(*cursor));
+    print!("{}", mine_field.live_update(&[*cursor]));
+    print!("Arrow keys move, space reveals, f flags, c chords, q gives up");
+    io::stdout().flush().ok()?;
+
+    let raw_mode = RawMode::enable()?;
+    let mut buffer = [0; 8];
+    loop {
+        let read = io::stdin().read(&mut buffer).ok().filter(|&read| read > 0)?;
+        let Some(key) = parse_key(&buffer[..read]) else {
+            continue;
+        };
+
+        // Commands use the coordinates the player would have typed
+        let (x, y) = *cursor;
+        let command = match key {
+            Key::Reveal => Command::Reveal(x + 1, mine_field.row_label(y)),
+            Key::Flag => Command::Flag(x + 1, mine_field.row_label(y)),
+            Key::Chord => Command::Chord(x + 1, mine_field.row_label(y)),
+            Key::Quit => Command::Quit,
+            Key::Exit => return None,
+            Key::Up | Key::Down | Key::Left | Key::Right => {
+                *cursor = move_cursor(*cursor, key, width, height);
+                mine_field.set_cursor(Some(*cursor));
+                print!("{}", mine_field.live_update(&[(x, y), *cursor]));
+                io::stdout().flush().ok()?;
+                continue;
             }
-            println!("|{:2}", y + 1);
-        }
+        };
+
+        drop(raw_mode);
+        println!();
+        return Some(command);
+    }
+}
 
-        // Print bottom border
-        print!("  +");
-        for _ in 0..width {
-            print!("---");
+/// Keeps the terminal in raw mode, where key presses arrive one by one without being echoed,
+/// until it is dropped
+#[cfg(unix)]
+struct RawMode(libc::termios);
+
+#[cfg(unix)]
+impl RawMode {
+    /// Switches stdin to raw mode, returns None if it isn't a terminal
+    fn enable() -> Option<Self> {
+        // SAFETY: termios is plain data, filled in by tcgetattr before it is read
+        let mut termios: libc::termios = unsafe { std::mem::zeroed() };
+        // SAFETY: the pointers are valid for the duration of the calls
+        unsafe {
+            if libc::tcgetattr(libc::STDIN_FILENO, &mut termios) != 0 {
+                return None;
+            }
+            let original = termios;
+            libc::cfmakeraw(&mut termios);
+            if libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &termios) != 0 {
+                return None;
+            }
+            Some(RawMode(original))
         }
-        println!("+");
+    }
+}
 
-        // Print bottom coordinates
-        print!("    ");
-        for x in 1..=width {
-            print!("{:2} ", x);
+#[cfg(unix)]
+impl Drop for RawMode {
+    /// Restores the terminal settings from before raw mode
+    fn drop(&mut self) {
+        // SAFETY: the settings were read by tcgetattr and the pointer is valid for the call
+        unsafe {
+            libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &self.0);
         }
-        println!();
     }
 }
 
-/// Gets user input and parses it to the specified type
-fn get_input<T: FromStr>(prompt: &str) -> T 
-where
-    <T as FromStr>::Err: std::fmt::Debug,
-{
-    loop {
+/// Raw mode isn't supported here, so cursor mode is never entered
+#[cfg(not(unix))]
+struct RawMode;
+
+#[cfg(not(unix))]
+impl RawMode {
+    fn enable() -> Option<Self> {
+        None
+    }
+}
+
+/// Prints the prompt and reads a line from the user
+/// In a terminal the line can be edited and earlier lines recalled with the arrow keys
+/// Returns None if the input was closed (EOF) or couldn't be read
+fn read_line(prompt: &str) -> Option<String> {
+    // Echo the lines of a replay file so the output reads like a played game
+    let line = REPLAY_INPUT.with_borrow_mut(|replay| replay.as_mut().map(Iterator::next));
+    if let Some(line) = line {
         print!("{}", prompt);
-        io::stdout().flush().unwrap();
+        println!("{}", line.as_deref().unwrap_or_default());
+        return line;
+    }
 
-        let mut input = String::new();
-        io::stdin().read_line(&mut input).unwrap();
+    thread_local! {
+        static EDITOR: RefCell<Option<DefaultEditor>> = RefCell::new(
+            io::stdin().is_terminal().then(|| DefaultEditor::new().ok()).flatten()
+        );
+    }
 
+    let line = EDITOR.with_borrow_mut(|editor| {
+        editor.as_mut().map(|editor| {
+            let line = editor.readline(prompt).ok()?;
+            if !line.trim().is_empty() {
+                // Losing the history entry isn't worth interrupting the game for
+                let _ = editor.add_history_entry(line.as_str());
+            }
+            Some(line)
+        })
+    });
+    if let Some(line) = line {
+        return line;
+    }
+
+    // Plain input when reading from a pipe or file
+    print!("{}", prompt);
+    io::stdout().flush().ok()?;
+
+    let mut input = String::new();
+    match io::stdin().read_line(&mut input) {
+        Ok(0) | Err(_) => {
+            println!();
+            None
+        }
+        Ok(_) => Some(input),
+    }
+}
+
+/// Gets user input and parses it to the specified type
+/// Returns None if the input was closed
+fn get_input<T: FromStr>(prompt: &str) -> Option<T> {
+    loop {
+        let input = read_line(prompt)?;
         if let Ok(value) = input.trim().parse() {
-            return value;
+            return Some(value);
         }
         println!("Invalid input, please try again");
     }
 }
 
-/// Gets a pair of coordinates from the user
-fn get_input_vec2(prompt: &str, max_x: usize, max_y: usize) -> (usize, usize) {
+/// Asks the user a yes or no question
+/// Returns None if the input was closed
+fn get_yes_no(prompt: &str) -> Option<bool> {
     loop {
-        print!("{}", prompt);
-        io::stdout().flush().unwrap();
+        let answer: String = get_input(prompt)?;
+        match answer.to_lowercase().as_str() {
+            "y" | "yes" => return Some(true),
+            "n" | "no" => return Some(false),
+            _ => println!("Please answer y or n"),
+        }
+    }
+}
 
-        let mut input = String::new();
-        io::stdin().read_line(&mut input).unwrap();
+/// Gets a pair of coordinates from the user
+/// Returns None if the input was closed
+fn get_input_vec2(prompt: &str, max_x: usize, max_y: usize) -> Option<(usize, usize)> {
+    loop {
+        let input = read_line(prompt)?;
+        let parts: Vec<&str> = input.split_whitespace().collect();
+        match parse_vec2(&parts, max_x, max_y) {
+            Ok(vec2) => return Some(vec2),
+            Err(error) => println!("{}", error),
+        }
+    }
+}
 
-        let parts: Vec<&str> = input.trim().split_whitespace().collect();
-        if parts.len() != 2 {
-            println!("Please enter two numbers separated by space");
-            continue;
+/// Gets a command from the user, asking again until a valid one is entered
+/// Returns None if the input was closed
+fn get_command(prompt: &str, max_x: usize, max_y: usize) -> Option<Command> {
+    loop {
+        let input = read_line(prompt)?;
+        match parse_command(&input, max_x, max_y) {
+            Ok(command) => return Some(command),
+            Err(error) => println!("{}", error),
         }
+    }
+}
 
-        if let (Ok(x), Ok(y)) = (parts[0].parse::<usize>(), parts[1].parse::<usize>()) {
-            if x > 0 && x <= max_x && y > 0 && y <= max_y {
-                return (x, y);
-            }
-            println!("Numbers must be in range: (1..{}) (1..{})", max_x, max_y);
-        } else {
-            println!("Invalid input, please enter two numbers");
+/// Parses a reveal, flag, chord, explain, demo, undo, save, load, hint, mini-map, mark, auto solve, restart or give up command
+/// Returns a description of the problem if the command is invalid
+fn parse_command(input: &str, max_x: usize, max_y: usize) -> Result<Command, String> {
+    // Save and load take an optional file name instead of coordinates
+    let mut parts: Vec<&str> = input.split_whitespace().collect();
+    match parts.as_slice() {
+        ["h"] => return Ok(Command::Hint),
+        ["map"] => return Ok(Command::Map),
+        ["m"] => return Ok(Command::Annotate),
+        ["auto"] => return Ok(Command::Auto),
+        ["demo"] => return Ok(Command::Demo),
+        ["u"] => return Ok(Command::Undo),
+        ["restart"] => return Ok(Command::Restart),
+        ["q"] | ["give", "up"] => return Ok(Command::Quit),
+        ["s"] => return Ok(Command::Save(SAVE_PATH.to_string())),
+        ["s", path] => return Ok(Command::Save(path.to_string())),
+        ["l"] => return Ok(Command::Load(SAVE_PATH.to_string())),
+        ["l", path] => return Ok(Command::Load(path.to_string())),
+        _ => {}
+    }
+
+    // An optional leading "f", "c" or "explain" turns the reveal into a flag toggle, a chord or
+    // an explanation, a leading "r" spells the reveal out
+    let command: fn(usize, usize) -> Command = match parts.first() {
+        Some(&"f") => Command::Flag,
+        Some(&"c") => Command::Chord,
+        Some(&"explain") => Command::Explain,
+        _ => Command::Reveal,
+    };
+    if matches!(parts.first(), Some(&"f" | &"c" | &"r" | &"explain")) {
+        parts.remove(0);
+    }
+
+    // A single word is a cell label like "b3" instead of two numbers
+    let (x, y) = match parts.as_slice() {
+        [label] => parse_label(label, max_x, max_y)?,
+        _ => parse_vec2(&parts, max_x, max_y)?,
+    };
+    Ok(command(x, y))
+}
+
+/// Parses two whitespace separated numbers in range
+/// Returns a description of the problem if they are invalid
+fn parse_vec2(parts: &[&str], max_x: usize, max_y: usize) -> Result<(usize, usize), String> {
+    let [x, y] = parts else {
+        return Err("Please enter two numbers separated by space".to_string());
+    };
+
+    match (x.parse::<usize>(), y.parse::<usize>()) {
+        (Ok(x), Ok(y)) if x > 0 && x <= max_x && y > 0 && y <= max_y => Ok((x, y)),
+        (Ok(_), Ok(_)) => Err(format!("Numbers must be in range: (1..{}) (1..{})", max_x, max_y)),
+        _ => Err("Invalid input, please enter two numbers".to_string()),
+    }
+}
+
+/// Parses a cell label made of column letters followed by a row number, like "b3", in range
+/// Returns a description of the problem if it is invalid
+fn parse_label(label: &str, max_x: usize, max_y: usize) -> Result<(usize, usize), String> {
+    let (letters, row) = label.split_at(label.find(|c: char| c.is_ascii_digit()).unwrap_or(label.len()));
+    if letters.is_empty() || letters.len() > 3 || !letters.chars().all(|c| c.is_ascii_alphabetic()) {
+        return Err("Invalid input, please enter two numbers or a column letter and a row number".to_string());
+    }
+
+    // Letters count like A=1 ... Z=26, AA=27, matching the printed column labels
+    let x = letters
+        .bytes()
+        .fold(0, |x, letter| x * 26 + (letter.to_ascii_uppercase() - b'A') as usize + 1);
+    match row.parse::<usize>() {
+        Ok(y) if x <= max_x && y > 0 && y <= max_y => Ok((x, y)),
+        Ok(_) => Err(format!("Cell must be in range: (1..{}) (1..{})", max_x, max_y)),
+        Err(_) => Err("Invalid input, please enter a column letter followed by a row number".to_string()),
+    }
+}
+
+/// Gets the game parameters from the user, either from a preset or entered manually
+/// Returns None if the input was closed
+fn get_settings() -> Option<(usize, usize, usize)> {
+    let difficulty: Difficulty = get_input("Difficulty (beginner, intermediate, expert, custom): ")?;
+    difficulty.settings().or_else(get_custom_settings)
+}
+
+/// Gets custom game parameters from the user
+/// Returns None if the input was closed
+fn get_custom_settings() -> Option<(usize, usize, usize)> {
+    let (width, height) = get_input_vec2("Mine field size (width height): ", MAX_SIZE, MAX_SIZE)?;
+    let (width, height) = get_valid_size(width, height);
+
+    // Keep asking until the mine count fits on the board
+    loop {
+        let mine_count: usize = get_input("Mine count: ")?;
+        match get_valid_mine_count(width, height, mine_count) {
+            Ok(mine_count) => return Some((width, height, mine_count)),
+            Err(error) => println!("{}", error),
         }
     }
 }
@@ -258,7 +965,238 @@ fn get_valid_size(width: usize, height: usize) -> (usize, usize) {
     )
 }
 
-/// Ensures the mine count is reasonable for the board size
-fn get_valid_mine_count(width: usize, height: usize, mine_count: usize) -> usize {
-    mine_count.clamp(2, width * height / 10)
+/// Checks a mine field size given on the command line and adjusts it like one entered at the prompt
+fn get_checked_size(width: usize, height: usize) -> Result<(usize, usize), String> {
+    if width == 0 || width > MAX_SIZE || height == 0 || height > MAX_SIZE {
+        return Err(format!("Mine field size must be in range: (1..{}) (1..{})", MAX_SIZE, MAX_SIZE));
+    }
+    Ok(get_valid_size(width, height))
+}
+
+/// Ensures the mine count fits on the board while leaving at least one safe cell for the first reveal
+/// Returns a description of the problem if the requested count is impossible
+fn get_valid_mine_count(width: usize, height: usize, mine_count: usize) -> Result<usize, String> {
+    // Saturate so an empty board reports that no mines fit instead of overflowing
+    let max_mines = (width * height).saturating_sub(1);
+    if mine_count == 0 {
+        Err("There has to be at least one mine".to_string())
+    } else if mine_count > max_mines {
+        Err(format!("A {}x{} mine field fits at most {} mines", width, height, max_mines))
+    } else {
+        Ok(mine_count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parses the given command line arguments
+    fn args(args: &[&str]) -> Result<Args, String> {
+        parse_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn parse_args_accepts_a_size_and_mine_count() {
+        assert_eq!(args(&["9", "9", "10"]).unwrap().settings, Some((9, 9, 10)));
+        assert_eq!(args(&[]).unwrap().settings, None);
+    }
+
+    #[test]
+    fn parse_args_rejects_too_few_or_too_many_numbers() {
+        assert!(args(&["9", "9"]).is_err());
+        assert!(args(&["9", "9", "10", "4"]).is_err());
+    }
+
+    #[test]
+    fn parse_args_rejects_non_numeric_arguments() {
+        assert_eq!(args(&["9", "nine", "10"]).err(), Some("Unknown argument: nine".to_string()));
+        assert!(args(&["--seed", "abc"]).is_err());
+    }
+
+    #[test]
+    fn mine_count_fits_all_but_one_cell() {
+        assert!(get_valid_mine_count(9, 9, 82).is_err());
+        assert!(get_valid_mine_count(9, 9, 81).is_err());
+        assert_eq!(get_valid_mine_count(9, 9, 80), Ok(80));
+        assert!(get_valid_mine_count(9, 9, 0).is_err());
+    }
+
+    #[test]
+    fn parse_args_reads_the_orthogonal_flag() {
+        assert!(args(&["--orthogonal"]).unwrap().orthogonal);
+        assert!(!args(&[]).unwrap().orthogonal);
+    }
+
+    #[test]
+    fn parse_command_reads_lines_as_the_editor_returns_them() {
+        // Plain input keeps the line break, the line editor strips it
+        assert_eq!(parse_command("3 4\n", 9, 9), Ok(Command::Reveal(3, 4)));
+        assert_eq!(parse_command("  f 3 4  ", 9, 9), Ok(Command::Flag(3, 4)));
+        assert_eq!(parse_command("q\r\n", 9, 9), Ok(Command::Quit));
+        assert!(parse_command("\n", 9, 9).is_err());
+    }
+
+    #[test]
+    fn parse_command_reads_every_command() {
+        let parse = |input| parse_command(input, 9, 5);
+        assert_eq!(parse("2 3"), Ok(Command::Reveal(2, 3)));
+        assert_eq!(parse("r 2 3"), Ok(Command::Reveal(2, 3)));
+        assert_eq!(parse("f 2 3"), Ok(Command::Flag(2, 3)));
+        assert_eq!(parse("c 9 5"), Ok(Command::Chord(9, 5)));
+        assert_eq!(parse("explain 1 1"), Ok(Command::Explain(1, 1)));
+        assert_eq!(parse("h"), Ok(Command::Hint));
+        assert_eq!(parse("map"), Ok(Command::Map));
+        assert_eq!(parse("m"), Ok(Command::Annotate));
+        assert_eq!(parse("auto"), Ok(Command::Auto));
+        assert_eq!(parse("demo"), Ok(Command::Demo));
+        assert_eq!(parse("u"), Ok(Command::Undo));
+        assert_eq!(parse("restart"), Ok(Command::Restart));
+        assert_eq!(parse("q"), Ok(Command::Quit));
+        assert_eq!(parse("give up"), Ok(Command::Quit));
+        assert_eq!(parse("s"), Ok(Command::Save(SAVE_PATH.to_string())));
+        assert_eq!(parse("s game.txt"), Ok(Command::Save("game.txt".to_string())));
+        assert_eq!(parse("l"), Ok(Command::Load(SAVE_PATH.to_string())));
+        assert_eq!(parse("l game.txt"), Ok(Command::Load("game.txt".to_string())));
+    }
+
+    #[test]
+    fn parse_command_rejects_malformed_input() {
+        let parse = |input| parse_command(input, 9, 5);
+        assert_eq!(parse(""), Err("Please enter two numbers separated by space".to_string()));
+        assert_eq!(parse("1 2 3"), Err("Please enter two numbers separated by space".to_string()));
+        assert_eq!(parse("f"), Err("Please enter two numbers separated by space".to_string()));
+        assert_eq!(parse("x y"), Err("Invalid input, please enter two numbers".to_string()));
+        assert_eq!(parse("-1 2"), Err("Invalid input, please enter two numbers".to_string()));
+        assert!(parse("hello").is_err());
+    }
+
+    #[test]
+    fn parse_command_rejects_coordinates_off_the_board() {
+        let parse = |input| parse_command(input, 9, 5);
+        let out_of_range = Err("Numbers must be in range: (1..9) (1..5)".to_string());
+        assert_eq!(parse("0 1"), out_of_range);
+        assert_eq!(parse("1 0"), out_of_range);
+        assert_eq!(parse("10 1"), out_of_range);
+        assert_eq!(parse("f 1 6"), out_of_range);
+    }
+
+    #[test]
+    fn mine_count_bound_holds_on_tiny_boards() {
+        assert_eq!(get_valid_mine_count(3, 3, 1), Ok(1));
+        assert_eq!(get_valid_mine_count(3, 3, 8), Ok(8));
+        assert_eq!(get_valid_mine_count(3, 3, 9), Err("A 3x3 mine field fits at most 8 mines".to_string()));
+        assert_eq!(get_valid_mine_count(0, 0, 1), Err("A 0x0 mine field fits at most 0 mines".to_string()));
+    }
+
+    #[test]
+    fn parse_label_counts_columns_from_a() {
+        // The third column is "c", index 2 once the typed coordinates are made 0-based
+        assert_eq!(parse_label("c4", 9, 9), Ok((3, 4)));
+        assert_eq!(parse_label("C4", 9, 9), Ok((3, 4)));
+        assert_eq!(parse_label("a1", 9, 9), Ok((1, 1)));
+        assert_eq!(parse_label("z2", 30, 9), Ok((26, 2)));
+        assert_eq!(parse_label("aa2", 30, 9), Ok((27, 2)));
+        assert!(parse_label("c", 9, 9).is_err());
+        assert!(parse_label("c0", 9, 9).is_err());
+        assert!(parse_label("4c", 9, 9).is_err());
+        assert!(parse_label("abcd1", 9, 9).is_err());
+    }
+
+    #[test]
+    fn parse_command_reads_cell_labels() {
+        let parse = |input| parse_command(input, 9, 5);
+        assert_eq!(parse("b3"), Ok(Command::Reveal(2, 3)));
+        assert_eq!(parse("f b3"), Ok(Command::Flag(2, 3)));
+        assert_eq!(parse("j1"), Err("Cell must be in range: (1..9) (1..5)".to_string()));
+        assert_eq!(parse("a6"), Err("Cell must be in range: (1..9) (1..5)".to_string()));
+        assert!(parse("b").is_err());
+        assert!(parse("3b").is_err());
+    }
+
+    #[test]
+    fn a_past_deadline_has_expired() {
+        assert!(is_expired(Instant::now() - Duration::from_secs(1)));
+        assert!(!is_expired(Instant::now() + Duration::from_secs(60)));
+    }
+
+    #[test]
+    fn parse_args_reads_the_time_limit() {
+        assert_eq!(args(&["--time-limit", "90"]).unwrap().time_limit, Some(Duration::from_secs(90)));
+        assert_eq!(args(&[]).unwrap().time_limit, None);
+        assert!(args(&["--time-limit", "soon"]).is_err());
+    }
+
+    #[test]
+    fn parse_key_reads_arrows_and_letters() {
+        assert_eq!(parse_key(b"\x1b[A"), Some(Key::Up));
+        assert_eq!(parse_key(b"\x1b[B"), Some(Key::Down));
+        assert_eq!(parse_key(b"\x1b[D"), Some(Key::Left));
+        assert_eq!(parse_key(b"\x1b[C"), Some(Key::Right));
+        assert_eq!(parse_key(b"k"), Some(Key::Up));
+        assert_eq!(parse_key(b"l"), Some(Key::Right));
+        assert_eq!(parse_key(b" "), Some(Key::Reveal));
+        assert_eq!(parse_key(b"\r"), Some(Key::Reveal));
+        assert_eq!(parse_key(b"f"), Some(Key::Flag));
+        assert_eq!(parse_key(b"c"), Some(Key::Chord));
+        assert_eq!(parse_key(b"q"), Some(Key::Quit));
+        assert_eq!(parse_key(&[3]), Some(Key::Exit));
+        assert_eq!(parse_key(&[4]), Some(Key::Exit));
+        assert_eq!(parse_key(b"x"), None);
+        assert_eq!(parse_key(b"\x1b"), None);
+    }
+
+    #[test]
+    fn move_cursor_stops_at_the_edges() {
+        assert_eq!(move_cursor((0, 0), Key::Up, 4, 3), (0, 0));
+        assert_eq!(move_cursor((0, 0), Key::Left, 4, 3), (0, 0));
+        assert_eq!(move_cursor((3, 2), Key::Down, 4, 3), (3, 2));
+        assert_eq!(move_cursor((3, 2), Key::Right, 4, 3), (3, 2));
+        assert_eq!(move_cursor((1, 1), Key::Up, 4, 3), (1, 0));
+        assert_eq!(move_cursor((1, 1), Key::Down, 4, 3), (1, 2));
+        assert_eq!(move_cursor((1, 1), Key::Left, 4, 3), (0, 1));
+        assert_eq!(move_cursor((1, 1), Key::Right, 4, 3), (2, 1));
+        assert_eq!(move_cursor((1, 1), Key::Flag, 4, 3), (1, 1));
+    }
+
+    #[test]
+    fn tutorial_rule_asks_for_a_correct_flag_first() {
+        let mut mine_field: MineField = "*..\n...".parse().unwrap();
+        mine_field.set_flood(false);
+        assert_eq!(tutorial_rule(&mine_field, &Command::Reveal(3, 2)), Ok(()));
+
+        mine_field.reveal(2, 1).unwrap();
+        assert_eq!(
+            tutorial_rule(&mine_field, &Command::Reveal(3, 1)),
+            Err("Flag a mine you are sure of before revealing more cells".to_string())
+        );
+        assert_eq!(tutorial_rule(&mine_field, &Command::Flag(1, 1)), Ok(()));
+
+        mine_field.flag(1, 1).unwrap();
+        assert_eq!(
+            tutorial_rule(&mine_field, &Command::Chord(2, 2)),
+            Err("The flag at (2 2) is not on a mine, remove it before revealing more cells".to_string())
+        );
+        mine_field.flag(1, 1).unwrap();
+        mine_field.flag(1, 1).unwrap();
+        mine_field.flag(0, 0).unwrap();
+        assert_eq!(tutorial_rule(&mine_field, &Command::Reveal(3, 1)), Ok(()));
+    }
+
+    #[test]
+    fn the_first_opened_cell_is_never_a_mine() {
+        for seed in 0..100 {
+            let mut mine_field = MineField::new_seeded(9, 9, 70, seed).unwrap();
+            mine_field.flag(0, 0).unwrap();
+            fill_before_reveal(&mut mine_field, 0, 0, false);
+            assert!(!mine_field.is_filled());
+            assert_eq!(mine_field.reveal(0, 0), Ok(GameState::Playing));
+
+            let (x, y) = (1 + seed as usize % 8, seed as usize / 8 % 9);
+            fill_before_reveal(&mut mine_field, x, y, false);
+            assert!(mine_field.is_filled());
+            assert_eq!(mine_field.reveal(x, y), Ok(GameState::Playing), "seed {}", seed);
+            assert!(matches!(mine_field.cell_at(x, y), Some(CellView::Revealed(_))));
+        }
+    }
 }
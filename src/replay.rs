@@ -0,0 +1,125 @@
+use crate::{BoardError, GameState, MineField};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// A single move made by the player, as recorded for replays
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    Reveal { x: usize, y: usize },  // Reveal the cell
+    Flag { x: usize, y: usize },    // Cycle the mark on the cell
+    Chord { x: usize, y: usize },   // Reveal the neighbors of a number whose mines are all flagged
+}
+
+impl MineField {
+    /// Returns every move made by the player since the game started, oldest first
+    /// Undone moves are left out
+    pub fn actions(&self) -> &[Action] {
+        &self.actions
+    }
+
+    /// Makes the given moves in order, stopping early once the game is won or lost
+    /// An empty mine field is filled around the first revealed cell, like the game does,
+    /// so replaying on a board created with the same seed reproduces the recorded game
+    /// Returns the state of the game after the last move
+    pub fn replay(&mut self, actions: &[Action]) -> Result<GameState, BoardError> {
+        for &action in actions {
+            let state = match action {
+                Action::Reveal { x, y } => {
+                    if !self.is_filled() {
+                        self.check_bounds(x, y)?;
+                        self.fill(x, y);
+                    }
+                    self.reveal(x, y)?
+                }
+                Action::Flag { x, y } => {
+                    self.flag(x, y)?;
+                    self.state()
+                }
+                Action::Chord { x, y } => self.chord(x, y)?,
+            };
+            if state != GameState::Playing {
+                return Ok(state);
+            }
+        }
+        Ok(self.state())
+    }
+
+    /// Saves the moves made by the player to the given file, one move per line
+    pub fn save_replay(&self, path: &Path) -> io::Result<()> {
+        let mut data = String::new();
+        for action in &self.actions {
+            let (kind, x, y) = match *action {
+                Action::Reveal { x, y } => ('r', x, y),
+                Action::Flag { x, y } => ('f', x, y),
+                Action::Chord { x, y } => ('c', x, y),
+            };
+            data.push_str(&format!("{} {} {}\n", kind, x, y));
+        }
+        fs::write(path, data)
+    }
+
+    /// Loads the moves previously written by `save_replay` from the given file
+    pub fn load_replay(path: &Path) -> io::Result<Vec<Action>> {
+        let invalid = || io::Error::new(io::ErrorKind::InvalidData, "invalid move");
+        let data = fs::read_to_string(path)?;
+
+        let mut actions = Vec::new();
+        for line in data.lines().filter(|line| !line.trim().is_empty()) {
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            let [kind, x, y] = parts[..] else {
+                return Err(invalid());
+            };
+            let x = x.parse().map_err(|_| invalid())?;
+            let y = y.parse().map_err(|_| invalid())?;
+            actions.push(match kind {
+                "r" => Action::Reveal { x, y },
+                "f" => Action::Flag { x, y },
+                "c" => Action::Chord { x, y },
+                _ => return Err(invalid()),
+            });
+        }
+        Ok(actions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_saved_replay_reproduces_the_game() {
+        let mut played = MineField::new_seeded(9, 9, 10, 5).unwrap();
+        played.fill(4, 4);
+        played.reveal(4, 4).unwrap();
+        played.flag(8, 8).unwrap();
+        for _ in 0..3 {
+            let (x, y, _) = played
+                .cells()
+                .find(|(_, _, cell)| !cell.is_mine && !cell.is_revealed && !cell.is_flagged())
+                .unwrap();
+            played.reveal(x, y).unwrap();
+        }
+        played.flag(8, 8).unwrap();
+
+        let path = std::env::temp_dir().join(format!("minesweeper-{}-replay", std::process::id()));
+        played.save_replay(&path).unwrap();
+        let actions = MineField::load_replay(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(actions, played.actions());
+
+        let mut replayed = MineField::new_seeded(9, 9, 10, 5).unwrap();
+        assert_eq!(replayed.replay(&actions), Ok(played.state()));
+        assert_eq!(replayed.field, played.field);
+        assert_eq!(replayed.moves(), played.moves());
+        assert_eq!(replayed.to_layout_string(), played.to_layout_string());
+    }
+
+    #[test]
+    fn load_replay_rejects_unknown_moves() {
+        let path = std::env::temp_dir().join(format!("minesweeper-{}-bad-replay", std::process::id()));
+        fs::write(&path, "r 1 2\nx 3 4\n").unwrap();
+        assert_eq!(MineField::load_replay(&path).unwrap_err().kind(), io::ErrorKind::InvalidData);
+        fs::remove_file(&path).unwrap();
+    }
+}
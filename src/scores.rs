@@ -0,0 +1,61 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::Duration;
+
+/// Best completion times keyed by the width, height and mine count of the board
+pub type Scores = BTreeMap<(usize, usize, usize), Duration>;
+
+/// Loads the best times previously written by `save_scores` from the given file
+/// A missing or corrupt file gives an empty leaderboard so a broken file never stops the game
+pub fn load_scores(path: &Path) -> Scores {
+    let Ok(data) = fs::read_to_string(path) else {
+        return Scores::new();
+    };
+
+    // Every line holds the width, height, mine count and best time in milliseconds
+    let mut scores = Scores::new();
+    for line in data.lines().filter(|line| !line.trim().is_empty()) {
+        let values: Option<Vec<u64>> = line.split_whitespace().map(|value| value.parse().ok()).collect();
+        let Some(&[width, height, mine_count, millis]) = values.as_deref() else {
+            return Scores::new();
+        };
+        scores.insert(
+            (width as usize, height as usize, mine_count as usize),
+            Duration::from_millis(millis),
+        );
+    }
+    scores
+}
+
+/// Saves the best times to the given file
+pub fn save_scores(path: &Path, scores: &Scores) -> io::Result<()> {
+    let mut data = String::new();
+    for (&(width, height, mine_count), time) in scores {
+        data.push_str(&format!("{} {} {} {}\n", width, height, mine_count, time.as_millis()));
+    }
+    fs::write(path, data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scores_survive_saving_and_loading() {
+        let path = std::env::temp_dir().join(format!("minesweeper-{}-scores", std::process::id()));
+        let mut scores = Scores::new();
+        scores.insert((9, 9, 10), Duration::from_millis(42_317));
+        scores.insert((30, 16, 99), Duration::from_secs(600));
+
+        save_scores(&path, &scores).unwrap();
+        assert_eq!(load_scores(&path), scores);
+
+        // A corrupt file is treated like a missing one
+        fs::write(&path, "9 9 10 oops\n").unwrap();
+        assert!(load_scores(&path).is_empty());
+        fs::remove_file(&path).unwrap();
+        assert!(load_scores(&path).is_empty());
+    }
+}
@@ -0,0 +1,123 @@
+use crate::{Adjacency, Cell, Distribution, GameState, MineField, WinRule};
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// The parts of a mine field that make up a game, as they are serialized
+/// Display settings, the random number generator, the clock and the undo history are left out
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "MineField")]
+struct MineFieldData {
+    width: usize,
+    height: usize,
+    mine_count: usize,
+    wrap: bool,
+    adjacency: Adjacency,
+    win_rule: WinRule,
+    flood: bool,
+    safe_radius: usize,
+    min_first_opening: usize,
+    distribution: Distribution,
+    moves: usize,
+    state: GameState,
+    cells: Vec<Vec<Cell>>,          // Rows of cells, top to bottom
+    mask: Option<Vec<Vec<bool>>>,   // Which cells are part of the board, `None` if all of them are
+}
+
+impl Serialize for MineField {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        MineFieldData {
+            width: self.width,
+            height: self.height,
+            mine_count: self.mine_count,
+            wrap: self.wrap,
+            adjacency: self.adjacency,
+            win_rule: self.win_rule,
+            flood: self.flood,
+            safe_radius: self.safe_radius,
+            min_first_opening: self.min_first_opening,
+            distribution: self.distribution,
+            moves: self.moves,
+            state: self.state,
+            cells: self.field.iter().map(|row| row.to_vec()).collect(),
+            mask: self.mask.as_ref().map(|mask| mask.iter().map(|row| row.to_vec()).collect()),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for MineField {
+    /// Rebuilds the game with a fresh random number generator and the default display settings
+    /// Fails if the rows don't match the dimensions or the adjacent mine counts don't match the mines
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let data = MineFieldData::deserialize(deserializer)?;
+        if !has_shape(&data.cells, data.width, data.height) {
+            return Err(D::Error::custom("the cells don't match the width and height"));
+        }
+        if let Some(mask) = &data.mask
+            && !has_shape(mask, data.width, data.height)
+        {
+            return Err(D::Error::custom("the mask doesn't match the width and height"));
+        }
+
+        let mut mine_field = MineField::new(data.width, data.height, data.mine_count).map_err(D::Error::custom)?;
+        mine_field.wrap = data.wrap;
+        mine_field.adjacency = data.adjacency;
+        mine_field.win_rule = data.win_rule;
+        mine_field.flood = data.flood;
+        mine_field.safe_radius = data.safe_radius;
+        mine_field.min_first_opening = data.min_first_opening;
+        mine_field.distribution = data.distribution;
+        mine_field.moves = data.moves;
+        mine_field.state = data.state;
+        mine_field.field = data.cells.into_iter().map(Vec::into_boxed_slice).collect();
+        mine_field.mask = data.mask.map(|mask| mask.into_iter().map(Vec::into_boxed_slice).collect());
+        mine_field.count_safe_cells();
+
+        if mine_field.validate().is_err() {
+            return Err(D::Error::custom("the adjacent mine counts don't match the mines"));
+        }
+        Ok(mine_field)
+    }
+}
+
+/// Returns true if there are `height` rows of `width` values each
+fn has_shape<T>(rows: &[Vec<T>], width: usize, height: usize) -> bool {
+    rows.len() == height && rows.iter().all(|row| row.len() == width)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{GameState, MineField};
+
+    #[test]
+    fn a_game_survives_a_json_round_trip() {
+        let mut mine_field = MineField::new_seeded(8, 6, 7, 12).unwrap();
+        mine_field.set_mask(|x, y| (x, y) != (7, 0));
+        mine_field.set_flood(false);
+        mine_field.fill(3, 3);
+        mine_field.reveal(3, 3).unwrap();
+        mine_field.flag(0, 5).unwrap();
+
+        let json = serde_json::to_string(&mine_field).unwrap();
+        let loaded: MineField = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.field, mine_field.field);
+        assert_eq!(loaded.to_layout_string(), mine_field.to_layout_string());
+        assert_eq!(loaded.moves(), 1);
+        assert_eq!(loaded.state(), GameState::Playing);
+        assert_eq!(loaded.revealed_count(), mine_field.revealed_count());
+        assert!(loaded.field[5][0].is_flagged());
+        assert!(!loaded.is_active(7, 0));
+    }
+
+    #[test]
+    fn inconsistent_json_is_rejected() {
+        let mine_field: MineField = "*.\n..".parse().unwrap();
+        let json = serde_json::to_string(&mine_field).unwrap();
+        assert!(serde_json::from_str::<MineField>(&json.replace("\"width\":2", "\"width\":3")).is_err());
+
+        let miscounted = json.replacen("\"adjacent_mines\":1", "\"adjacent_mines\":2", 1);
+        assert_ne!(miscounted, json);
+        let error = serde_json::from_str::<MineField>(&miscounted).err().unwrap();
+        assert!(error.to_string().contains("the adjacent mine counts don't match the mines"));
+    }
+}
@@ -0,0 +1,521 @@
+use crate::{BoardError, GameState, Mark, MineField};
+use log::{debug, trace, warn};
+
+const MAX_NO_GUESS_ATTEMPTS: usize = 1000;  // Boards generated before giving up on a no-guess board
+
+impl MineField {
+    /// Returns the unrevealed, unflagged cells with the lowest estimated chance of holding a mine
+    /// This is a naive heuristic rather than a full solver: every revealed number spreads its
+    /// remaining mines evenly across its hidden neighbors, and cells no number touches get the
+    /// average density of the remaining mines
+    pub fn safest_cells(&self) -> Vec<(usize, usize)> {
+        let width = self.width();
+        let height = self.height();
+
+        // Collect the estimates every revealed number gives for each of its hidden neighbors
+        let mut estimates = vec![Vec::new(); width * height];
+        for y in 0..height {
+            for x in 0..width {
+                let cell = self.field[y][x];
+                if !cell.is_revealed || cell.is_mine {
+                    continue;
+                }
+
+                let hidden: Vec<(usize, usize)> = self
+                    .neighbors(x, y)
+                    .filter(|&(x, y)| !self.field[y][x].is_revealed && !self.field[y][x].is_flagged())
+                    .collect();
+                let flagged = self.flagged_neighbors(x, y).len();
+                let remaining = (cell.adjacent_mines as usize).saturating_sub(flagged);
+
+                for &(x, y) in &hidden {
+                    estimates[y * width + x].push(remaining as f64 / hidden.len() as f64);
+                }
+            }
+        }
+
+        // Cells without any neighboring number share the remaining mines evenly
+        let unknown = self
+            .cells()
+            .filter(|&(x, y, cell)| !cell.is_revealed && !cell.is_flagged() && self.is_active(x, y))
+            .count();
+        let density = self.remaining_mines().max(0) as f64 / unknown.max(1) as f64;
+
+        // Combine the estimates of each hidden cell, a number proving it safe or a mine always wins
+        let mut probabilities = Vec::new();
+        for y in 0..height {
+            for x in 0..width {
+                let cell = self.field[y][x];
+                if cell.is_revealed || cell.is_flagged() || !self.is_active(x, y) {
+                    continue;
+                }
+
+                let estimates = &estimates[y * width + x];
+                let probability = if estimates.is_empty() {
+                    density
+                } else if estimates.contains(&0.0) {
+                    0.0
+                } else if estimates.contains(&1.0) {
+                    1.0
+                } else {
+                    estimates.iter().sum::<f64>() / estimates.len() as f64
+                };
+                probabilities.push((x, y, probability));
+            }
+        }
+
+        let lowest = probabilities.iter().map(|&(_, _, p)| p).fold(f64::INFINITY, f64::min);
+        probabilities
+            .into_iter()
+            .filter(|&(_, _, p)| p <= lowest + f64::EPSILON)
+            .map(|(x, y, _)| (x, y))
+            .collect()
+    }
+}
+
+impl MineField {
+    /// Applies the two basic deductions once to every revealed number:
+    /// if all of its mines are flagged its other hidden neighbors are revealed,
+    /// and if it has exactly as many hidden neighbors as mines they are all flagged
+    /// Returns true if any cell was revealed or flagged, nothing is done once the game is over
+    pub fn solve_step(&mut self) -> bool {
+        if self.state != GameState::Playing {
+            return false;
+        }
+        let mut progress = false;
+
+        for y in 0..self.height() {
+            for x in 0..self.width() {
+                // Empty cells are checked too, their neighbors stay covered when flooding is disabled
+                let cell = self.field[y][x];
+                if !cell.is_revealed || cell.is_mine {
+                    continue;
+                }
+
+                let hidden = self.unrevealed_neighbors(x, y);
+                let (flagged, unflagged): (Vec<_>, Vec<_>) =
+                    hidden.iter().partition(|&&(x, y)| self.field[y][x].is_flagged());
+                if unflagged.is_empty() {
+                    continue;
+                }
+
+                // The solver moves like the player would, so its moves can be undone and replayed
+                let mines = cell.adjacent_mines as usize;
+                if flagged.len() == mines {
+                    // Every mine is accounted for, the rest is safe
+                    // Cells an earlier flood opened are skipped, and misplaced flags can make a reveal hit a mine
+                    let mut revealed = 0;
+                    for (x, y) in unflagged {
+                        if self.state == GameState::Playing && !self.field[y][x].is_revealed {
+                            revealed += self.reveal_cells(x, y).expect("the game is in progress").1.len();
+                        }
+                    }
+                    trace!("solver revealed {} cells around the {} at ({}, {})", revealed, mines, x, y);
+                    progress = true;
+                } else if hidden.len() == mines {
+                    // Every hidden neighbor has to be a mine
+                    // Flagging cycles through the marks, so a question mark takes two steps
+                    trace!("solver flagged {} cells around the {} at ({}, {})", unflagged.len(), mines, x, y);
+                    for (x, y) in unflagged {
+                        while self.state == GameState::Playing && !self.field[y][x].is_flagged() {
+                            self.flag(x, y).expect("the game is in progress");
+                        }
+                    }
+                    progress = true;
+                }
+                if self.state != GameState::Playing {
+                    return true;
+                }
+            }
+        }
+        progress
+    }
+
+    /// Applies solver steps until no more progress can be made without guessing
+    pub fn solve(&mut self) {
+        while self.solve_step() {}
+    }
+
+    /// Marks what the two basic deductions prove without revealing anything: provable mines are flagged
+    /// and provably safe cells get a question mark, leaving the clicking to the player
+    /// Returns the number of cells that were marked
+    pub fn annotate(&mut self) -> usize {
+        if self.state != GameState::Playing {
+            return 0;
+        }
+
+        // Repeat until nothing changes, as new flags can prove more cells safe
+        let mut marked = 0;
+        let mut progress = true;
+        while progress {
+            progress = false;
+            for y in 0..self.height() {
+                for x in 0..self.width() {
+                    let cell = self.field[y][x];
+                    if !cell.is_revealed || cell.is_mine {
+                        continue;
+                    }
+
+                    let hidden = self.unrevealed_neighbors(x, y);
+                    let flagged = self.flagged_neighbors(x, y).len();
+
+                    let mines = cell.adjacent_mines as usize;
+                    let mark = if hidden.len() == mines {
+                        Mark::Flag
+                    } else if flagged == mines {
+                        Mark::Question
+                    } else {
+                        continue;
+                    };
+
+                    // Leave flags alone when marking safe cells, a misplaced flag is for the player to find
+                    for (x, y) in hidden {
+                        let current = &mut self.field[y][x].mark;
+                        if *current != mark && *current != Mark::Flag {
+                            *current = mark;
+                            marked += 1;
+                            progress = true;
+                        }
+                    }
+                }
+            }
+        }
+        marked
+    }
+
+    /// Describes which revealed number proves the hidden cell safe or a mine, using the same two
+    /// deductions as `solve_step`
+    /// Returns None if the cell is revealed, not part of the board or no neighboring number decides it
+    pub fn explain(&self, x: usize, y: usize) -> Option<String> {
+        if self.check_bounds(x, y).is_err() || self.field[y][x].is_revealed {
+            return None;
+        }
+        let label = self.cell_label(x, y);
+
+        // Neighborhoods are symmetric, so the numbers around the cell are the ones it counts towards
+        for (nx, ny) in self.neighbors(x, y) {
+            let cell = self.field[ny][nx];
+            if !cell.is_revealed || cell.is_mine {
+                continue;
+            }
+
+            let hidden = self.unrevealed_neighbors(nx, ny);
+            let flagged = self.flagged_neighbors(nx, ny).len();
+            let mines = cell.adjacent_mines as usize;
+            let plural = if mines == 1 { "" } else { "s" };
+            let number = self.cell_label(nx, ny);
+
+            if mines == 0 {
+                return Some(format!("Cell ({}) is safe because the empty cell at ({}) has no mines around it", label, number));
+            }
+            if !self.field[y][x].is_flagged() && flagged == mines {
+                return Some(format!(
+                    "Cell ({}) is safe because the {} at ({}) already has {} flagged neighbor{}",
+                    label, mines, number, mines, plural
+                ));
+            }
+            if hidden.len() == mines {
+                return Some(format!(
+                    "Cell ({}) is a mine because the {} at ({}) has exactly {} hidden neighbor{}",
+                    label, mines, number, mines, plural
+                ));
+            }
+        }
+        None
+    }
+}
+
+impl MineField {
+    /// Counts the mine arrangements that agree with every revealed number and the total mine count,
+    /// stopping once `limit` is reached, so a count of 1 proves the position is fully determined
+    /// Flags are ignored since they may be wrong
+    /// The search backtracks over the hidden cells next to revealed numbers and grows exponentially
+    /// with them, so it is meant for small boards or small frontiers
+    pub fn solution_count(&self, limit: usize) -> usize {
+        let hidden: Vec<(usize, usize)> = self
+            .cells()
+            .filter(|&(x, y, cell)| self.is_active(x, y) && !cell.is_revealed)
+            .map(|(x, y, _)| (x, y))
+            .collect();
+        let revealed_mines = self.cells().filter(|(_, _, cell)| cell.is_revealed && cell.is_mine).count();
+
+        // Every revealed number constrains its hidden neighbors, which make up the frontier
+        let width = self.width();
+        let mut frontier_index = vec![None; width * self.height()];
+        let mut cell_constraints: Vec<Vec<usize>> = Vec::new();
+        let mut needed = Vec::new();
+        let mut open = Vec::new();
+        for (x, y, cell) in self.cells() {
+            if !cell.is_revealed || cell.is_mine {
+                continue;
+            }
+
+            let neighbors = self.unrevealed_neighbors(x, y);
+            let known_mines = self
+                .neighbors(x, y)
+                .filter(|&(x, y)| self.field[y][x].is_revealed && self.field[y][x].is_mine)
+                .count();
+            let mines = (cell.adjacent_mines as usize).saturating_sub(known_mines);
+            if mines > neighbors.len() {
+                return 0;
+            }
+            if neighbors.is_empty() {
+                continue;
+            }
+
+            let constraint = needed.len();
+            needed.push(mines);
+            open.push(neighbors.len());
+            for (x, y) in neighbors {
+                let index = *frontier_index[y * width + x].get_or_insert_with(|| {
+                    cell_constraints.push(Vec::new());
+                    cell_constraints.len() - 1
+                });
+                cell_constraints[index].push(constraint);
+            }
+        }
+
+        let mut search = SolutionSearch {
+            others: hidden.len() - cell_constraints.len(),
+            cell_constraints,
+            needed,
+            open,
+            mines: self.mine_count().saturating_sub(revealed_mines),
+            limit,
+            count: 0,
+        };
+        search.search(0, 0);
+        search.count
+    }
+
+    /// Creates a new mine field that can be solved without guessing when starting from `first`
+    /// Returns an error if the mine field would have no cells
+    pub fn new_no_guess(
+        width: usize,
+        height: usize,
+        mine_count: usize,
+        first: (usize, usize),
+    ) -> Result<Self, BoardError> {
+        let mut mine_field = MineField::new(width, height, mine_count)?;
+        mine_field.fill_no_guess(first.0, first.1);
+        Ok(mine_field)
+    }
+
+    /// Fills the mine field like `fill`, retrying until the board can be solved by `solve`
+    /// after revealing (safe_x, safe_y)
+    /// Returns false and keeps the last generated board if none is found within the attempt limit
+    pub fn fill_no_guess(&mut self, safe_x: usize, safe_y: usize) -> bool {
+        for attempt in 1..=MAX_NO_GUESS_ATTEMPTS {
+            self.fill(safe_x, safe_y);
+
+            // Play a copy of the board with the solver, so the moves it makes don't reach the player's game
+            let mut trial = self.clone();
+            trial.open(safe_x, safe_y, &mut Vec::new());
+            trial.solve();
+            if trial.all_safe_revealed() {
+                debug!("found a board solvable without guessing in {} attempt(s)", attempt);
+                return true;
+            }
+            trace!("attempt {} needs a guess", attempt);
+        }
+        warn!("no board solvable without guessing found in {} attempts", MAX_NO_GUESS_ATTEMPTS);
+        false
+    }
+}
+
+/// State of the backtracking search in `solution_count`
+struct SolutionSearch {
+    cell_constraints: Vec<Vec<usize>>,  // Numbers each frontier cell counts towards
+    needed: Vec<usize>,  // Mines each number still needs among its unassigned cells
+    open: Vec<usize>,    // Cells of each number that haven't been assigned yet
+    others: usize,       // Hidden cells no number touches
+    mines: usize,        // Mines hidden on the whole board
+    limit: usize,        // Count at which the search stops
+    count: usize,        // Arrangements found so far
+}
+
+impl SolutionSearch {
+    /// Tries both a mine and no mine on the frontier cell and the ones after it, placing `placed` mines so far
+    /// Once the frontier is assigned, the remaining mines can go on any of the other hidden cells
+    fn search(&mut self, cell: usize, placed: usize) {
+        if self.count >= self.limit {
+            return;
+        }
+        if cell == self.cell_constraints.len() {
+            if let Some(rest) = self.mines.checked_sub(placed) {
+                self.count = (self.count + binomial(self.others, rest, self.limit)).min(self.limit);
+            }
+            return;
+        }
+
+        for is_mine in [false, true] {
+            // A mine needs every number around it to still need one, no mine needs room for the rest
+            let fits = self.cell_constraints[cell]
+                .iter()
+                .all(|&c| if is_mine { self.needed[c] > 0 } else { self.needed[c] < self.open[c] });
+            if !fits || (is_mine && placed == self.mines) {
+                continue;
+            }
+
+            for &c in &self.cell_constraints[cell] {
+                self.open[c] -= 1;
+                self.needed[c] -= is_mine as usize;
+            }
+            self.search(cell + 1, placed + is_mine as usize);
+            for &c in &self.cell_constraints[cell] {
+                self.open[c] += 1;
+                self.needed[c] += is_mine as usize;
+            }
+        }
+    }
+}
+
+/// Returns the number of ways to choose `k` of `n` items, or `cap` if there are at least that many
+fn binomial(n: usize, k: usize, cap: usize) -> usize {
+    if k > n {
+        return 0;
+    }
+
+    // Each partial product is itself a binomial coefficient, so the division is always exact
+    let mut result: u128 = 1;
+    for i in 0..k.min(n - k) {
+        result = result * (n - i) as u128 / (i + 1) as u128;
+        if result >= cap as u128 {
+            return cap;
+        }
+    }
+    result as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{GameState, Mark, MineField};
+
+    #[test]
+    fn safest_cells_finds_a_provably_safe_neighbor() {
+        let mut mine_field: MineField = "*..".parse().unwrap();
+        mine_field.flag(0, 0).unwrap();
+        mine_field.reveal(1, 0).unwrap();
+        assert_eq!(mine_field.safest_cells(), vec![(2, 0)]);
+    }
+
+    #[test]
+    fn solve_clears_a_board_that_needs_no_guess() {
+        let mut mine_field: MineField = "**.\n...\n...".parse().unwrap();
+        mine_field.reveal(2, 2).unwrap();
+        assert!(!mine_field.is_won());
+        mine_field.solve();
+        assert!(mine_field.is_won());
+    }
+
+    #[test]
+    fn solve_stops_where_a_guess_is_needed() {
+        let mut mine_field: MineField = "*.\n..".parse().unwrap();
+        mine_field.reveal(1, 1).unwrap();
+        assert!(!mine_field.solve_step());
+        assert!(!mine_field.is_won());
+        assert_eq!(mine_field.field.iter().flatten().filter(|cell| cell.is_revealed).count(), 1);
+    }
+
+    #[test]
+    fn no_guess_boards_are_solvable_from_the_first_cell() {
+        for seed in 0..5 {
+            let mut mine_field = MineField::new_seeded(9, 9, 10, seed).unwrap();
+            assert!(mine_field.fill_no_guess(4, 4));
+            mine_field.reveal(4, 4).unwrap();
+            mine_field.solve();
+            assert!(mine_field.is_won(), "seed {}", seed);
+        }
+
+        let mut mine_field = MineField::new_no_guess(9, 9, 10, (0, 0)).unwrap();
+        assert!(mine_field.field.iter().flatten().all(|cell| !cell.is_revealed));
+        mine_field.reveal(0, 0).unwrap();
+        mine_field.solve();
+        assert!(mine_field.is_won());
+    }
+
+    #[test]
+    fn annotate_flags_the_one_deducible_mine() {
+        let mut mine_field: MineField = "*...\n....".parse().unwrap();
+        mine_field.set_flood(false);
+        mine_field.reveal_many(&[(1, 0), (0, 1), (1, 1)]).unwrap();
+
+        // The 1 at (0, 1) has a single hidden neighbor, once it is flagged the other 1s prove their
+        // remaining neighbors safe
+        assert_eq!(mine_field.annotate(), 3);
+        assert_eq!(mine_field.field[0][0].mark, Mark::Flag);
+        assert_eq!(mine_field.field[0][2].mark, Mark::Question);
+        assert_eq!(mine_field.field[1][2].mark, Mark::Question);
+        assert_eq!(mine_field.field[0][3].mark, Mark::None);
+        assert_eq!(mine_field.cells().filter(|(_, _, cell)| cell.is_flagged()).count(), 1);
+        assert_eq!(mine_field.cells().filter(|(_, _, cell)| cell.is_revealed).count(), 3);
+    }
+
+    #[test]
+    fn explain_names_the_number_that_decides_the_cell() {
+        let mut mine_field: MineField = "*...\n....".parse().unwrap();
+        mine_field.set_flood(false);
+        mine_field.reveal_many(&[(1, 0), (0, 1), (1, 1)]).unwrap();
+        assert_eq!(
+            mine_field.explain(0, 0).as_deref(),
+            Some("Cell (1 1) is a mine because the 1 at (1 2) has exactly 1 hidden neighbor")
+        );
+        assert_eq!(mine_field.explain(2, 0), None);
+        assert_eq!(mine_field.explain(1, 0), None);
+
+        mine_field.flag(0, 0).unwrap();
+        assert_eq!(
+            mine_field.explain(2, 0).as_deref(),
+            Some("Cell (3 1) is safe because the 1 at (2 1) already has 1 flagged neighbor")
+        );
+        assert_eq!(mine_field.explain(3, 0), None);
+    }
+
+    #[test]
+    fn solution_count_is_one_on_a_determined_board() {
+        // The lone 1 at (0, 1) pins the mine, and the total mine count rules out the other cells
+        let mut mine_field: MineField = "*...\n....".parse().unwrap();
+        mine_field.set_flood(false);
+        mine_field.reveal_many(&[(1, 0), (0, 1), (1, 1)]).unwrap();
+        assert_eq!(mine_field.solution_count(10), 1);
+
+        // A 50/50 has two arrangements, and the limit cuts the count short
+        let mut mine_field: MineField = "*.\n..".parse().unwrap();
+        mine_field.set_flood(false);
+        mine_field.reveal_many(&[(1, 0), (1, 1)]).unwrap();
+        assert_eq!(mine_field.solution_count(10), 2);
+        assert_eq!(mine_field.solution_count(1), 1);
+    }
+
+    #[test]
+    fn solver_moves_can_be_undone_and_replayed() {
+        let start = || {
+            let mut mine_field = MineField::new_seeded(9, 9, 10, 3).unwrap();
+            mine_field.set_flood(false);
+            mine_field
+        };
+        let mut mine_field = start();
+        mine_field.fill(4, 4);
+        mine_field.reveal(4, 4).unwrap();
+        let before = mine_field.clone();
+        mine_field.solve();
+        assert_eq!(mine_field.state(), GameState::Playing);
+        assert_eq!(mine_field.moves(), 66);
+
+        // Replaying the recorded moves on the same seed reaches the same position
+        let mut replayed = start();
+        assert_eq!(replayed.replay(mine_field.actions()), Ok(GameState::Playing));
+        assert!(replayed == mine_field);
+        assert_eq!(replayed.moves(), mine_field.moves());
+
+        // Every reveal of the solver is undone on its own, while its flags stay like the player's do
+        for _ in before.moves()..mine_field.moves() {
+            assert!(mine_field.undo());
+        }
+        assert_eq!(mine_field.moves(), before.moves());
+        assert_eq!(mine_field.revealed_count(), before.revealed_count());
+        assert!(mine_field.cells().all(|(x, y, cell)| cell.is_revealed == before.field[y][x].is_revealed));
+        assert!(mine_field.undo());
+        assert!(!mine_field.undo());
+    }
+}
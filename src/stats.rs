@@ -0,0 +1,273 @@
+use crate::MineField;
+use std::time::Duration;
+
+/// Summary of how efficiently a game was played
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Stats {
+    pub bbbv: usize,         // 3BV of the board
+    pub clicks: usize,       // Reveal and chord actions taken by the player
+    pub elapsed: Duration,   // Time taken since the first reveal
+}
+
+impl Stats {
+    /// Returns the 3BV as a percentage of the clicks taken, 100% or more meaning no click was wasted
+    pub fn efficiency(&self) -> f64 {
+        if self.clicks == 0 {
+            return 0.0;
+        }
+        self.bbbv as f64 / self.clicks as f64 * 100.0
+    }
+
+    /// Returns the 3BV cleared per second
+    pub fn bbbv_per_second(&self) -> f64 {
+        let seconds = self.elapsed.as_secs_f64();
+        if seconds == 0.0 {
+            return 0.0;
+        }
+        self.bbbv as f64 / seconds
+    }
+}
+
+impl MineField {
+    /// Returns the 3BV of the board, the least number of clicks needed to clear it without chording:
+    /// one for every opening (a connected region of empty cells together with its numbered border)
+    /// plus one for every numbered cell that doesn't border an opening
+    pub fn bbbv(&self) -> usize {
+        let width = self.width();
+        let openings = self.openings();
+
+        // Every opening takes a single click and clears its empty cells and their numbered border
+        let mut covered = vec![false; width * self.height()];
+        for &(x, y) in openings.iter().flatten() {
+            covered[y * width + x] = true;
+            for (x, y) in self.neighbors(x, y) {
+                covered[y * width + x] = true;
+            }
+        }
+
+        // Every other numbered cell has to be clicked on its own
+        openings.len()
+            + self
+                .cells()
+                .filter(|&(x, y, cell)| !cell.is_mine && !covered[y * width + x] && self.is_active(x, y))
+                .count()
+    }
+
+    /// Returns the number of cells in the biggest connected region of empty cells, 0 if there is none
+    pub fn largest_opening(&self) -> usize {
+        self.openings().iter().map(Vec::len).max().unwrap_or(0)
+    }
+
+    /// Returns the number of cells revealing (x, y) would open on a board with nothing revealed yet,
+    /// counting the numbers around an empty region, or 0 if it holds a mine or isn't part of the mine field
+    pub fn opening_size(&self, x: usize, y: usize) -> usize {
+        if self.check_bounds(x, y).is_err() {
+            return 0;
+        }
+        let cell = self.field[y][x];
+        if cell.is_mine {
+            return 0;
+        }
+        if cell.adjacent_mines != 0 || !self.flood {
+            return 1;
+        }
+
+        // Flood the region like a reveal does, counting the numbered border without going past it
+        let width = self.width();
+        let mut visited = vec![false; width * self.height()];
+        visited[y * width + x] = true;
+        let mut size = 1;
+        let mut pending = vec![(x, y)];
+        while let Some((x, y)) = pending.pop() {
+            for (x, y) in self.neighbors(x, y) {
+                if !visited[y * width + x] {
+                    visited[y * width + x] = true;
+                    size += 1;
+                    if self.field[y][x].adjacent_mines == 0 {
+                        pending.push((x, y));
+                    }
+                }
+            }
+        }
+        size
+    }
+
+    /// Returns the empty cells of every connected region of empty cells on the solved board
+    fn openings(&self) -> Vec<Vec<(usize, usize)>> {
+        let width = self.width();
+        let is_empty = |x: usize, y: usize| {
+            let cell = self.field[y][x];
+            !cell.is_mine && cell.adjacent_mines == 0 && self.is_active(x, y)
+        };
+
+        let mut visited = vec![false; width * self.height()];
+        let mut openings = Vec::new();
+        for (x, y, _) in self.cells() {
+            if visited[y * width + x] || !is_empty(x, y) {
+                continue;
+            }
+
+            // Flood the region through its empty cells
+            visited[y * width + x] = true;
+            let mut opening = Vec::new();
+            let mut pending = vec![(x, y)];
+            while let Some((x, y)) = pending.pop() {
+                opening.push((x, y));
+                for (x, y) in self.neighbors(x, y) {
+                    if !visited[y * width + x] && is_empty(x, y) {
+                        visited[y * width + x] = true;
+                        pending.push((x, y));
+                    }
+                }
+            }
+            openings.push(opening);
+        }
+        openings
+    }
+
+    /// Rates how hard the solved board is, typically around 7 for a beginner board and 55 for an expert one
+    /// The rating is `100 * density * (3BV / safe cells) * (1 - largest opening / safe cells) * ln(cells)`:
+    /// denser boards and boards needing more clicks per safe cell are harder, a big opening makes a
+    /// board easier and larger boards take longer to get through
+    /// Returns 0 for a board without safe cells
+    pub fn difficulty_score(&self) -> f64 {
+        let cells = self.cells().filter(|&(x, y, _)| self.is_active(x, y)).count();
+        let mines = self.cells().filter(|&(x, y, cell)| cell.is_mine && self.is_active(x, y)).count();
+        let safe = cells - mines;
+        if safe == 0 {
+            return 0.0;
+        }
+
+        let density = mines as f64 / cells as f64;
+        let clicks_per_cell = self.bbbv() as f64 / safe as f64;
+        let opening_share = self.largest_opening() as f64 / safe as f64;
+        100.0 * density * clicks_per_cell * (1.0 - opening_share) * (cells as f64).ln()
+    }
+
+    /// Returns the 3BV, clicks and time of the game so far, usually asked for once it is won
+    pub fn stats(&self) -> Stats {
+        Stats {
+            bbbv: self.bbbv(),
+            clicks: self.moves(),
+            elapsed: self.elapsed(),
+        }
+    }
+
+    /// Fills the board `samples` times around the safe cell and returns how often each cell received
+    /// a mine, as a fraction of the samples
+    /// Useful to check that mines are spread uniformly, the board is left empty afterwards
+    pub fn mine_density(&mut self, samples: usize, safe_x: usize, safe_y: usize) -> Vec<Vec<f64>> {
+        let mut counts = vec![vec![0; self.width()]; self.height()];
+        for _ in 0..samples {
+            self.fill(safe_x, safe_y);
+            for (x, y, cell) in self.cells() {
+                if cell.is_mine {
+                    counts[y][x] += 1;
+                }
+            }
+        }
+        self.reset();
+
+        counts
+            .into_iter()
+            .map(|row| row.into_iter().map(|count| count as f64 / samples.max(1) as f64).collect())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Stats;
+    use crate::MineField;
+    use std::time::Duration;
+
+    #[test]
+    fn bbbv_counts_openings_and_lone_numbers() {
+        // Two openings whose numbered borders cover every number
+        let mine_field: MineField = "*...\n....\n...*".parse().unwrap();
+        assert_eq!(mine_field.bbbv(), 2);
+
+        // No openings, so every safe cell takes a click
+        let mine_field: MineField = "*.\n..".parse().unwrap();
+        assert_eq!(mine_field.bbbv(), 3);
+
+        // One opening plus the number between the two mines on the right, which borders no opening
+        let mine_field: MineField = "....*\n.....\n....*".parse().unwrap();
+        assert_eq!(mine_field.bbbv(), 2);
+    }
+
+    #[test]
+    fn clearing_in_3bv_clicks_is_fully_efficient() {
+        let mut mine_field: MineField = "*...\n....\n...*".parse().unwrap();
+        mine_field.reveal(3, 0).unwrap();
+        assert_eq!(mine_field.reveal(0, 2), Ok(crate::GameState::Won));
+
+        let stats = mine_field.stats();
+        assert_eq!((stats.bbbv, stats.clicks), (2, 2));
+        assert_eq!(stats.efficiency(), 100.0);
+    }
+
+    #[test]
+    fn wasted_clicks_lower_the_efficiency() {
+        let stats = Stats { bbbv: 3, clicks: 4, elapsed: Duration::from_secs(2) };
+        assert_eq!(stats.efficiency(), 75.0);
+        assert_eq!(stats.bbbv_per_second(), 1.5);
+        assert_eq!(Stats { bbbv: 3, clicks: 0, elapsed: Duration::ZERO }.efficiency(), 0.0);
+    }
+
+    #[test]
+    fn mines_are_spread_uniformly_outside_the_safe_area() {
+        let mut mine_field = MineField::new_seeded(4, 4, 3, 11).unwrap();
+        let density = mine_field.mine_density(10_000, 0, 0);
+
+        // The 4 cells around the corner are kept free, leaving 12 equally likely cells for 3 mines
+        for (y, row) in density.iter().enumerate() {
+            for (x, &frequency) in row.iter().enumerate() {
+                if x <= 1 && y <= 1 {
+                    assert_eq!(frequency, 0.0);
+                } else {
+                    assert!((frequency - 0.25).abs() < 0.02, "cell ({}, {}) got a mine {} of the time", x, y, frequency);
+                }
+            }
+        }
+        assert!(!mine_field.is_filled());
+    }
+
+    #[test]
+    fn largest_opening_picks_the_bigger_empty_region() {
+        // The empty cells left of the mines form one region of 6 cells, the right column one of 3
+        let mine_field: MineField = "...*..\n...*..\n...*..".parse().unwrap();
+        assert_eq!(mine_field.largest_opening(), 6);
+
+        let mine_field: MineField = "*.\n..".parse().unwrap();
+        assert_eq!(mine_field.largest_opening(), 0);
+    }
+
+    #[test]
+    fn dense_large_boards_score_harder_than_sparse_small_ones() {
+        let mut sparse = MineField::new_seeded(9, 9, 5, 1).unwrap();
+        sparse.fill(4, 4);
+        let mut dense = MineField::new_seeded(30, 16, 120, 1).unwrap();
+        dense.fill(15, 8);
+        assert!(sparse.difficulty_score() < dense.difficulty_score());
+
+        let safe_free: MineField = "*".parse().unwrap();
+        assert_eq!(safe_free.difficulty_score(), 0.0);
+    }
+
+    #[test]
+    fn opening_size_counts_the_numbered_border() {
+        let mine_field: MineField = "*...\n....\n....".parse().unwrap();
+        assert_eq!(mine_field.opening_size(3, 2), 11);
+        assert_eq!(mine_field.opening_size(1, 0), 1);
+        assert_eq!(mine_field.opening_size(0, 0), 0);
+    }
+
+    #[test]
+    fn opening_size_is_zero_off_the_board() {
+        let mine_field: MineField = "*.\n.#".parse().unwrap();
+        assert_eq!(mine_field.opening_size(2, 0), 0);
+        assert_eq!(mine_field.opening_size(0, 5), 0);
+        assert_eq!(mine_field.opening_size(1, 1), 0);
+    }
+}
@@ -0,0 +1,90 @@
+use crate::MineField;
+
+impl MineField {
+    /// Returns a copy of the mine layout rotated a quarter turn clockwise
+    pub fn rotate_90(&self) -> MineField {
+        let height = self.height();
+        self.transformed(height, self.width(), |x, y| (height - 1 - y, x))
+    }
+
+    /// Returns a copy of the mine layout mirrored left to right
+    pub fn flip_horizontal(&self) -> MineField {
+        let width = self.width();
+        self.transformed(width, self.height(), |x, y| (width - 1 - x, y))
+    }
+
+    /// Returns a copy of the mine layout mirrored top to bottom
+    pub fn flip_vertical(&self) -> MineField {
+        let height = self.height();
+        self.transformed(self.width(), height, |x, y| (x, height - 1 - y))
+    }
+
+    /// Returns an unplayed copy of the board with every mine and hole moved to the cell `map` gives
+    /// for it, recomputing the adjacent mine counts on the new board
+    fn transformed(&self, width: usize, height: usize, map: impl Fn(usize, usize) -> (usize, usize)) -> MineField {
+        let mut mine_field = MineField::with_rng(width, height, self.mine_count, self.rng.clone())
+            .expect("a transformed board keeps the number of cells");
+        mine_field.use_color = self.use_color;
+        mine_field.theme = self.theme;
+        mine_field.wrap = self.wrap;
+        mine_field.adjacency = self.adjacency;
+        mine_field.win_rule = self.win_rule;
+        mine_field.flood = self.flood;
+        mine_field.cell_width = self.cell_width;
+        mine_field.safe_radius = self.safe_radius;
+        mine_field.min_first_opening = self.min_first_opening;
+        mine_field.peek = self.peek;
+        mine_field.flag_overlay = self.flag_overlay;
+        mine_field.column_labels = self.column_labels;
+        mine_field.bottom_origin = self.bottom_origin;
+        mine_field.auto_flag = self.auto_flag;
+        mine_field.auto_chord_on_revealed = self.auto_chord_on_revealed;
+        mine_field.distribution = self.distribution;
+        mine_field.margins = self.margins;
+
+        // Cut the holes first so the mine counts skip them
+        for (x, y, _) in self.cells() {
+            if !self.is_active(x, y) {
+                let (x, y) = map(x, y);
+                mine_field.cut_hole(x, y);
+            }
+        }
+        for (x, y, cell) in self.cells() {
+            if cell.is_mine {
+                let (x, y) = map(x, y);
+                mine_field.place_mine(x, y);
+            }
+        }
+        mine_field.count_safe_cells();
+        mine_field
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::MineField;
+
+    #[test]
+    fn four_quarter_turns_give_the_original_layout() {
+        let mut mine_field = MineField::new_seeded(7, 4, 6, 3).unwrap();
+        mine_field.fill(0, 0);
+        let turned = mine_field.rotate_90().rotate_90().rotate_90().rotate_90();
+        assert_eq!(turned.to_solution_string(), mine_field.to_solution_string());
+        assert_eq!(turned.field, mine_field.field);
+    }
+
+    #[test]
+    fn a_corner_mine_moves_to_the_expected_corner() {
+        let mine_field: MineField = "*..\n...".parse().unwrap();
+
+        let rotated = mine_field.rotate_90();
+        assert_eq!((rotated.width(), rotated.height()), (2, 3));
+        assert_eq!(rotated.to_layout_string(), ".*\n..\n..\n");
+        assert_eq!(mine_field.flip_horizontal().to_layout_string(), "..*\n...\n");
+        assert_eq!(mine_field.flip_vertical().to_layout_string(), "...\n*..\n");
+
+        for transformed in [rotated, mine_field.flip_horizontal(), mine_field.flip_vertical()] {
+            assert_eq!(transformed.mine_count(), 1);
+        }
+    }
+}
@@ -0,0 +1,70 @@
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+/// Runs the game with the given arguments, feeding it `input` on stdin
+/// Best times are kept out of the real home directory
+fn run(args: &[&str], input: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_minesweeper"))
+        .args(args)
+        .env("HOME", std::env::temp_dir())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("failed to start the game");
+    child.stdin.take().unwrap().write_all(input.as_bytes()).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+#[test]
+fn end_of_input_exits_cleanly() {
+    // Closing the input at the first prompt or in the middle of a game
+    for input in ["", "b\n", "b\n5 5\n"] {
+        let output = run(&[], input);
+        assert!(output.status.success(), "{:?}", input);
+        assert!(!String::from_utf8_lossy(&output.stdout).contains("Invalid input"));
+    }
+}
+
+#[test]
+fn unicode_board_snapshot() {
+    let output = run(&["7", "7", "2", "--unicode", "--no-color"], "");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let row = |y| format!("{:2}│{}│{:2}", y, " ".repeat(21), y);
+    let expected = [
+        "     1  2  3  4  5  6  7 ".to_string(),
+        format!("  ┌{}┐", "─".repeat(21)),
+        row(1),
+        row(2),
+        row(3),
+        row(4),
+        row(5),
+        row(6),
+        row(7),
+        format!("  └{}┘", "─".repeat(21)),
+        "     1  2  3  4  5  6  7 ".to_string(),
+    ];
+    assert!(stdout.contains(&expected.join("\n")), "{}", stdout);
+}
+
+#[test]
+fn a_seeded_replay_file_plays_the_same_game() {
+    let path = std::env::temp_dir().join(format!("minesweeper-cli-{}-replay", std::process::id()));
+    std::fs::write(&path, "5 5\nf 1 9\nq\n").unwrap();
+    let args = ["9", "9", "10", "--seed", "3", "--no-color", "--replay-file", path.to_str().unwrap()];
+    let first = run(&args, "");
+    let second = run(&args, "");
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(first.status.success());
+    let stdout = String::from_utf8_lossy(&first.stdout);
+    assert_eq!(stdout, String::from_utf8_lossy(&second.stdout));
+
+    // The moves are echoed after their prompts, the flag is drawn and the final board shows every mine
+    assert!(stdout.contains("(q): f 1 9\n"));
+    assert!(stdout.contains("Mines left: 9   Moves: 1"));
+    assert!(stdout.contains(" 9|  F                        | 9\n"));
+    assert!(stdout.contains(" 1|  .  .  .  .  .  1  *      | 1\n"));
+    assert!(stdout.contains(" 9|  *  *                     | 9\n"));
+    assert!(stdout.contains("You gave up!"));
+}
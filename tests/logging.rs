@@ -0,0 +1,41 @@
+use minesweeper::MineField;
+use std::sync::Mutex;
+
+/// Keeps every record logged while the test runs, tagged with its level
+struct TestLogger(Mutex<Vec<String>>);
+
+impl log::Log for TestLogger {
+    fn enabled(&self, _: &log::Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &log::Record) {
+        let message = format!("{} {}", record.level(), record.args());
+        self.0.lock().unwrap().push(message);
+    }
+
+    fn flush(&self) {}
+}
+
+static LOGGER: TestLogger = TestLogger(Mutex::new(Vec::new()));
+
+#[test]
+fn filling_and_playing_are_logged() {
+    // The logger is global, so it lives in its own test binary and this is the only test in it
+    log::set_logger(&LOGGER).unwrap();
+    log::set_max_level(log::LevelFilter::Trace);
+
+    let mut mine_field = MineField::new_seeded(7, 5, 3, 2).unwrap();
+    mine_field.fill(6, 4);
+    mine_field.reveal(6, 4).unwrap();
+    let mut hopeless = MineField::new_seeded(8, 6, 10, 2).unwrap();
+    hopeless.set_min_first_opening(48);
+    hopeless.fill(7, 5);
+
+    let records = LOGGER.0.lock().unwrap();
+    let logged = |prefix: &str| records.iter().any(|record| record.starts_with(prefix));
+    assert!(logged("DEBUG placed 3 mines around (6, 4) in 1 attempt(s)"));
+    assert!(logged("DEBUG revealed (6, 4), opening"));
+    assert!(logged("TRACE attempt 1 opens"));
+    assert!(logged("WARN no board opens 48 cells from (7, 5), keeping the last one"));
+}